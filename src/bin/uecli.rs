@@ -1,17 +1,46 @@
 #![allow(clippy::unusual_byte_groupings)]
 #![allow(dead_code)]
 
-use clap::{ArgEnum, Parser, Subcommand};
+use clap::{ArgEnum, Args, Parser, Subcommand};
 use comfy_table::{presets::NOTHING, *};
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::io::{BufRead, Write};
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
+use std::{fs, io, process};
 use ue14500_toolkit::{
-    data::{Node, Nodes},
-    formats::{assembly, binary, FileType},
+    data::{CtrlKind, Node, Nodes, Word},
+    emulator::debugger::{Breakpoint, Command, Debugger, Stop},
+    emulator::devices::{self, Devices},
+    emulator::reader::{Feed, TapeReader},
+    emulator::vcd::Vcd,
+    emulator::{Machine, Step, PORT_BITS, SCRATCH_BITS},
+    formats::{
+        assembly, binary, binary::Split, debug, debug::DebugInfo, intel_hex,
+        logisim, mem, srec, tape, FileType,
+    },
 };
 
+#[cfg(feature = "json")]
+use ue14500_toolkit::formats::json;
+
 const TABLE_STYLE: &str = "││──├─┼┤│    ┬┴╭╮╰╯";
 
+const DEBUG_HELP: &str = "\
+step [N]       s  execute the next word, or the next N words
+next           n  execute words until one is not skipped
+continue [N]   c  execute words until something stops, at most N cycles
+break WORD     b  stop before the word at an index like 12
+break BIT      b  stop after a scratch bit like R3 changes
+watch REG      w  stop after RR, carry, IEN or OEN changes
+delete [ARG]   d  delete a breakpoint or watchpoint, or all of them
+info           i  list breakpoints and watchpoints
+print          p  show the machine state
+input BITS        set the external inputs, starting from the first
+reset             reset the machine and rewind the tape
+quit           q  leave the debugger";
+
 /// Command line tools for the Usagi Electric ue14500 processor
 #[derive(Parser, Debug, PartialEq)]
 #[clap(name = "uecli")]
@@ -21,10 +50,71 @@ struct Opt {
     #[clap(long, short = 'n')]
     #[clap(arg_enum, default_value = "bin")]
     numbers: NumberFormat,
+    /// Show builtin names like RR or PR0 for addresses
+    #[clap(long)]
+    symbols: bool,
+    #[clap(flatten)]
+    formats: Formats,
     #[clap(subcommand)]
     command: Cmd,
 }
 
+/// Options for reading and writing assembly and rom images
+#[derive(Args, Clone, Debug, PartialEq)]
+struct Formats {
+    /// Directory to search for files included by assembly
+    #[clap(long = "include", short = 'I', multiple_occurrences = true)]
+    #[clap(global = true)]
+    include_paths: Vec<PathBuf>,
+    /// Layout of words in Intel HEX and S-record files
+    #[clap(long, arg_enum, default_value = "word")]
+    layout: Layout,
+    /// Address of the first byte in S-record files
+    #[clap(long, default_value = "0", parse(try_from_str = parse_address))]
+    load_address: u32,
+    /// Number base of Verilog memory image files
+    #[clap(long, arg_enum, default_value = "hex")]
+    mem_radix: MemRadix,
+    /// Blank frames before the first word of a paper tape
+    #[clap(long, default_value = "16")]
+    leader: usize,
+    /// Blank frames after the last word of a paper tape
+    #[clap(long, default_value = "16")]
+    trailer: usize,
+    /// Frames of paper tape punched for each word
+    #[clap(long, default_value = "2")]
+    #[clap(possible_values = ["2", "3", "4", "6", "12"])]
+    frames_per_word: usize,
+}
+
+impl From<&Formats> for tape::Options {
+    fn from(formats: &Formats) -> tape::Options {
+        tape::Options::default()
+            .with_leader(formats.leader)
+            .with_trailer(formats.trailer)
+            .with_frames_per_word(formats.frames_per_word)
+    }
+}
+
+impl From<&Formats> for srec::Options {
+    fn from(formats: &Formats) -> srec::Options {
+        srec::Options::default()
+            .with_layout(formats.layout.into())
+            .with_load_address(formats.load_address)
+    }
+}
+
+fn parse_address(val: &str) -> Result<u32, String> {
+    let (digits, radix) = match val.get(..2) {
+        Some("0x") => (&val[2..], 16),
+        Some("0o") => (&val[2..], 8),
+        Some("0b") => (&val[2..], 2),
+        _ => (val, 10),
+    };
+
+    u32::from_str_radix(digits, radix).map_err(|error| error.to_string())
+}
+
 #[derive(Subcommand, Debug, PartialEq)]
 enum Cmd {
     /// Assemble binary
@@ -32,10 +122,29 @@ enum Cmd {
         /// List file contents
         #[clap(long, short = 'l')]
         list: bool,
+        /// Split words across byte wide chips, one binary per chip like
+        /// prog.0.bin, giving the word bits of each chip like 11-4,3-0
+        #[clap(long, parse(try_from_str), conflicts_with = "list")]
+        #[clap(min_values = 0, max_values = 1, require_equals = true)]
+        #[clap(default_missing_value = "11-4,3-0", value_name = "BITS")]
+        split: Option<Split>,
+        /// Write a binary container with a header and checksum
+        #[clap(long, conflicts_with = "split")]
+        container: bool,
+        /// Program name stored in a binary container
+        #[clap(long, requires = "container")]
+        name: Option<String>,
+        /// Notes stored in a binary container
+        #[clap(long, requires = "container")]
+        notes: Option<String>,
+        /// Write a debug file alongside the output, like prog.dbg, keeping
+        /// comments, symbols and source lines
+        #[clap(long, short = 'g')]
+        debug: bool,
         /// Assembly input
         #[clap(parse(try_from_str))]
         from: InputPath,
-        /// Binary output
+        /// Binary output, or a rom image in the format of its extension
         #[clap(parse(try_from_str))]
         into: OutputPath,
     },
@@ -45,7 +154,7 @@ enum Cmd {
         /// List file contents
         #[clap(long, short = 'l')]
         list: bool,
-        /// Binary input
+        /// Binary input, or a rom image in the format of its extension
         #[clap(parse(try_from_str))]
         from: InputPath,
         /// Assembly output
@@ -55,21 +164,129 @@ enum Cmd {
 
     /// List file contents
     List {
-        /// Binary input
+        /// Assembly, binary or rom image input
+        #[clap(parse(try_from_str))]
+        from: InputPath,
+    },
+
+    /// Run a program as a paper tape
+    Run {
+        /// Maximum number of cycles, defaults to one pass of the tape
+        #[clap(long, short = 'c')]
+        cycles: Option<u64>,
+        /// Halt after a word with the stop tape control bits
+        #[clap(long, short = 's')]
+        stop: bool,
+        /// Feed the tape through once rather than looping it
+        #[clap(long)]
+        once: bool,
+        /// External input bits, starting from the first input
+        #[clap(long, short = 'i', parse(try_from_str))]
+        inputs: Option<Bits>,
+        /// External input file, one line of bits per cycle
+        #[clap(long, parse(try_from_str), conflicts_with = "inputs")]
+        input_file: Option<InputPath>,
+        /// Write a value change dump of the run for waveform viewers
+        #[clap(long, parse(try_from_str))]
+        vcd: Option<OutputPath>,
+        /// Trace each cycle as a table or as CSV for spreadsheets
+        #[clap(long, arg_enum, value_name = "FORMAT")]
+        #[clap(min_values = 0, max_values = 1, require_equals = true)]
+        #[clap(default_missing_value = "table")]
+        trace: Option<TraceFormat>,
+        /// Attach io devices like switches and leds from a config file
+        #[clap(long, parse(try_from_str))]
+        devices: Option<InputPath>,
+        /// Assembly, binary or rom image input
+        #[clap(parse(try_from_str))]
+        from: InputPath,
+    },
+
+    /// Step through a program interactively
+    Debug {
+        /// Halt after a word with the stop tape control bits
+        #[clap(long, short = 's')]
+        stop: bool,
+        /// Feed the tape through once rather than looping it
+        #[clap(long)]
+        once: bool,
+        /// External input bits, starting from the first input
+        #[clap(long, short = 'i', parse(try_from_str))]
+        inputs: Option<Bits>,
+        /// Assembly, binary or rom image input
         #[clap(parse(try_from_str))]
         from: InputPath,
     },
 }
 
-#[derive(ArgEnum, Clone, Debug, PartialEq)]
+#[derive(ArgEnum, Clone, Copy, Debug, Default, PartialEq)]
 pub enum NumberFormat {
+    #[default]
     Bin,
     Oct,
+    Dec,
+    Hex,
+}
+
+impl From<NumberFormat> for assembly::Radix {
+    fn from(numbers: NumberFormat) -> assembly::Radix {
+        match numbers {
+            NumberFormat::Bin => assembly::Radix::Bin,
+            NumberFormat::Oct => assembly::Radix::Oct,
+            NumberFormat::Dec => assembly::Radix::Dec,
+            NumberFormat::Hex => assembly::Radix::Hex,
+        }
+    }
+}
+
+#[derive(ArgEnum, Clone, Copy, Debug, Default, PartialEq)]
+pub enum TraceFormat {
+    #[default]
+    Table,
+    Csv,
+}
+
+/// How the tape reader feeds a program while running it
+struct ReaderOptions {
+    feed: Feed,
+    stop: bool,
+}
+
+/// Files and traces written while running a program
+struct RunOutput {
+    vcd: Option<PathBuf>,
+    trace: Option<TraceFormat>,
+}
+
+#[derive(ArgEnum, Clone, Copy, Debug, Default, PartialEq)]
+pub enum Layout {
+    #[default]
+    Word,
+    Packed,
+}
+
+impl From<Layout> for binary::Layout {
+    fn from(layout: Layout) -> binary::Layout {
+        match layout {
+            Layout::Word => binary::Layout::Word,
+            Layout::Packed => binary::Layout::Packed,
+        }
+    }
+}
+
+#[derive(ArgEnum, Clone, Copy, Debug, Default, PartialEq)]
+pub enum MemRadix {
+    #[default]
+    Hex,
+    Bin,
 }
 
-impl Default for NumberFormat {
-    fn default() -> Self {
-        NumberFormat::Bin
+impl From<MemRadix> for mem::Radix {
+    fn from(radix: MemRadix) -> mem::Radix {
+        match radix {
+            MemRadix::Hex => mem::Radix::Hex,
+            MemRadix::Bin => mem::Radix::Bin,
+        }
     }
 }
 
@@ -107,6 +324,31 @@ impl FromStr for OutputPath {
     }
 }
 
+#[derive(Debug, PartialEq)]
+pub struct Bits(pub [bool; PORT_BITS]);
+
+impl FromStr for Bits {
+    type Err = String;
+
+    fn from_str(val: &str) -> Result<Bits, Self::Err> {
+        let mut bits = [false; PORT_BITS];
+
+        if val.len() > PORT_BITS {
+            return Err(format!("expected at most {} bits", PORT_BITS));
+        }
+
+        for (bit, char) in bits.iter_mut().zip(val.chars()) {
+            *bit = match char {
+                '0' => false,
+                '1' => true,
+                _ => return Err("expected bits of 0 or 1".into()),
+            };
+        }
+
+        Ok(Bits(bits))
+    }
+}
+
 fn validate_file(val: &str) -> Result<PathBuf, String> {
     match PathBuf::from_str(val) {
         Ok(path) => Ok(path),
@@ -122,6 +364,13 @@ fn validate_file_readable(path: &Path) -> Result<(), String> {
 }
 
 fn validate_file_writable(path: &Path) -> Result<(), String> {
+    if !path.exists() {
+        return match path.parent() {
+            Some(dir) if dir.as_os_str().is_empty() || dir.is_dir() => Ok(()),
+            _ => Err("expected an existing directory".into()),
+        };
+    }
+
     match path.metadata() {
         Ok(meta) => match (!meta.permissions().readonly(), !path.is_dir()) {
             (true, true) => Ok(()),
@@ -133,18 +382,34 @@ fn validate_file_writable(path: &Path) -> Result<(), String> {
 }
 
 fn main() {
-    let Opt { numbers, command } = Opt::parse();
+    let Opt {
+        numbers,
+        symbols,
+        formats,
+        command,
+    } = Opt::parse();
 
     match command {
         Cmd::Asm {
             list,
+            split,
+            container,
+            name,
+            notes,
+            debug,
             from: InputPath(from),
             into: OutputPath(into),
         } => {
-            run_asm(from, into.clone());
+            let container =
+                container.then_some(binary::Metadata { name, notes });
+
+            let (nodes, info) =
+                run_asm(from, into, split, container, debug, &formats);
 
+            // list the assembled nodes rather than the output, which only
+            // keeps macro expansions and comments with a debug file
             if list {
-                run_list(numbers, into)
+                print_list(numbers, symbols, nodes, Some(info))
             }
         }
         Cmd::Dsm {
@@ -152,44 +417,720 @@ fn main() {
             from: InputPath(from),
             into: OutputPath(into),
         } => {
-            run_dsm(from, into.clone());
+            run_dsm(numbers, symbols, &formats, from, into.clone());
 
             if list {
-                run_list(numbers, into)
+                run_list(numbers, symbols, &formats, into)
             }
         }
         Cmd::List {
             from: InputPath(from),
-        } => run_list(numbers, from),
+        } => run_list(numbers, symbols, &formats, from),
+        Cmd::Run {
+            cycles,
+            stop,
+            once,
+            inputs,
+            input_file,
+            vcd,
+            trace,
+            devices,
+            from: InputPath(from),
+        } => {
+            let inputs = match (inputs, input_file) {
+                (Some(bits), _) => vec![bits],
+                (_, Some(InputPath(path))) => read_bits(path),
+                _ => Vec::new(),
+            };
+            let devices = match devices {
+                Some(InputPath(path)) => or_exit(
+                    devices::read_file(path.clone()).map_err(io_error(&path)),
+                ),
+                None => Devices::default(),
+            };
+            let reader = ReaderOptions {
+                feed: if once { Feed::Once } else { Feed::Loop },
+                stop,
+            };
+            let output = RunOutput {
+                vcd: vcd.map(|OutputPath(path)| path),
+                trace,
+            };
+
+            run_tape(from, &formats, cycles, reader, inputs, devices, output)
+        }
+        Cmd::Debug {
+            stop,
+            once,
+            inputs,
+            from: InputPath(from),
+        } => {
+            let reader = ReaderOptions {
+                feed: if once { Feed::Once } else { Feed::Loop },
+                stop,
+            };
+
+            run_debug(numbers, symbols, &formats, from, reader, inputs)
+        }
+    }
+}
+
+/// unwrap a result or print the error and exit
+fn or_exit<T, E: Display>(result: Result<T, E>) -> T {
+    match result {
+        Ok(val) => val,
+        Err(error) => {
+            eprintln!("{}", error);
+            process::exit(1)
+        }
+    }
+}
+
+fn io_error(path: &Path) -> impl Fn(io::Error) -> String + '_ {
+    move |error| format!("error: {}: {}", path.display(), error)
+}
+
+fn read_assembly(from: PathBuf, include_paths: &[PathBuf]) -> Nodes {
+    or_exit(assembly::read_file_with(from, include_paths))
+}
+
+/// read the debug file kept alongside a binary, warning about and ignoring
+/// one that can not be used with the nodes read from it
+fn read_debug(from: &Path, nodes: &Nodes) -> Option<DebugInfo> {
+    match debug::read_sidecar(from, nodes) {
+        Ok(debug) => debug,
+        Err(error) => {
+            eprintln!(
+                "warning: {}: {}, ignoring it",
+                DebugInfo::path(from).display(),
+                error
+            );
+
+            None
+        }
     }
 }
 
-fn run_asm(from: PathBuf, into: PathBuf) {
-    binary::write_file(
-        into,
-        assembly::read_file(from).expect("error reading assembly"),
+fn read_binary(from: PathBuf, formats: &Formats) -> Nodes {
+    let nodes = match FileType::try_from(from.clone()) {
+        Ok(FileType::IntelHex) => {
+            intel_hex::read_file(from.clone(), formats.layout.into())
+        }
+        Ok(FileType::SRecord) => srec::read_file(from.clone(), formats.into()),
+        Ok(FileType::Mem) => {
+            mem::read_file(from.clone(), formats.mem_radix.into())
+        }
+        Ok(FileType::Logisim) => logisim::read_file(from.clone()),
+        Ok(FileType::Tape) => tape::read_file(from.clone(), formats.into()),
+        Ok(FileType::RawTape) => {
+            tape::read_raw_file(from.clone(), formats.into())
+        }
+        #[cfg(feature = "json")]
+        Ok(FileType::Json) => json::read_file(from.clone()),
+        #[cfg(not(feature = "json"))]
+        Ok(FileType::Json) => Err(json_disabled()),
+        _ => binary::read_file(from.clone()),
+    };
+
+    or_exit(nodes.map_err(io_error(&from)))
+}
+
+fn write_binary(into: PathBuf, nodes: Nodes, formats: &Formats) {
+    let result = match FileType::try_from(into.clone()) {
+        Ok(FileType::IntelHex) => {
+            intel_hex::write_file(into.clone(), nodes, formats.layout.into())
+        }
+        Ok(FileType::SRecord) => {
+            srec::write_file(into.clone(), nodes, formats.into())
+        }
+        Ok(FileType::Mem) => {
+            mem::write_file(into.clone(), nodes, formats.mem_radix.into())
+        }
+        Ok(FileType::Logisim) => logisim::write_file(into.clone(), nodes),
+        Ok(FileType::Tape) => {
+            tape::write_file(into.clone(), nodes, formats.into())
+        }
+        Ok(FileType::RawTape) => {
+            tape::write_raw_file(into.clone(), nodes, formats.into())
+        }
+        #[cfg(feature = "json")]
+        Ok(FileType::Json) => json::write_file(into.clone(), &nodes),
+        #[cfg(not(feature = "json"))]
+        Ok(FileType::Json) => Err(json_disabled()),
+        _ => binary::write_file(into.clone(), nodes),
+    };
+
+    or_exit(result.map_err(io_error(&into)));
+}
+
+#[cfg(not(feature = "json"))]
+fn json_disabled() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        "json files need the json feature",
     )
-    .expect("error writing binary");
 }
 
-fn run_dsm(_from: PathBuf, _into: PathBuf) {
-    println!("disassembly not yet implemented")
+fn read_nodes(from: PathBuf, formats: &Formats) -> Nodes {
+    read_nodes_debug(from, formats).0
 }
 
-fn run_list(numbers: NumberFormat, from: PathBuf) {
-    use NumberFormat::*;
+fn read_nodes_debug(
+    from: PathBuf,
+    formats: &Formats,
+) -> (Nodes, Option<DebugInfo>) {
+    let file_type = or_exit(FileType::try_from(from.clone()).map_err(|_| {
+        format!(
+            "error: {}: expected an assembly, binary or rom image file",
+            from.display()
+        )
+    }));
 
-    let Nodes(nodes) = match FileType::try_from(from.clone())
-        .expect("assembly or binary file")
-    {
+    match file_type {
         FileType::Assembly => {
-            assembly::read_file(from).expect("error reading assembly")
+            let (nodes, debug) = or_exit(assembly::read_file_debug(
+                from,
+                &formats.include_paths,
+            ));
+
+            (nodes, Some(debug))
+        }
+        _ => {
+            let nodes = read_binary(from.clone(), formats);
+
+            match read_debug(&from, &nodes) {
+                Some(debug) => (debug.annotate(nodes), Some(debug)),
+                None => (nodes, None),
+            }
+        }
+    }
+}
+
+fn read_bits(from: PathBuf) -> Vec<Bits> {
+    or_exit(fs::read_to_string(&from).map_err(io_error(&from)))
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            or_exit(Bits::from_str(line).map_err(|error| {
+                format!("error: {}: {}", from.display(), error)
+            }))
+        })
+        .collect()
+}
+
+fn format_bits(bits: &[bool]) -> String {
+    bits.iter()
+        .map(|bit| if *bit { '1' } else { '0' })
+        .collect()
+}
+
+fn run_asm(
+    from: PathBuf,
+    into: PathBuf,
+    split: Option<Split>,
+    container: Option<binary::Metadata>,
+    debug: bool,
+    formats: &Formats,
+) -> (Nodes, DebugInfo) {
+    // containers are only recognised when reading binary files back
+    let binary_output = matches!(
+        FileType::try_from(into.clone()),
+        Ok(FileType::Binary) | Err(_)
+    );
+
+    if container.is_some() && !binary_output {
+        eprintln!(
+            "error: {}: a container can only be written to a binary file",
+            into.display()
+        );
+        process::exit(1);
+    }
+
+    let (nodes, info) =
+        or_exit(assembly::read_file_debug(from, &formats.include_paths));
+
+    let path = DebugInfo::path(&into);
+
+    // a debug file left from an earlier build would describe another program
+    if debug {
+        or_exit(
+            debug::write_file(path.clone(), &info).map_err(io_error(&path)),
+        );
+    } else if path.is_file() {
+        or_exit(fs::remove_file(&path).map_err(io_error(&path)));
+    }
+
+    match (split, container) {
+        (Some(split), _) => {
+            or_exit(
+                binary::write_split(&into, nodes.clone(), &split)
+                    .map_err(io_error(&into)),
+            );
+        }
+        (_, Some(metadata)) => or_exit(
+            binary::write_container_file(
+                into.clone(),
+                nodes.clone(),
+                &metadata,
+            )
+            .map_err(io_error(&into)),
+        ),
+        _ => write_binary(into, nodes.clone(), formats),
+    }
+
+    (nodes, info)
+}
+
+fn run_dsm(
+    numbers: NumberFormat,
+    symbols: bool,
+    formats: &Formats,
+    from: PathBuf,
+    into: PathBuf,
+) {
+    let nodes = read_binary(from.clone(), formats);
+    let style = assembly::Style::default()
+        .with_radix(numbers.into())
+        .with_symbols(symbols);
+    let result = match read_debug(&from, &nodes) {
+        Some(info) => fs::File::create(&into).and_then(|mut file| {
+            debug::disassemble(&mut file, &nodes, &info, &style)
+        }),
+        None => assembly::write_file(into.clone(), &nodes, style),
+    };
+
+    or_exit(result.map_err(io_error(&into)));
+}
+
+fn run_tape(
+    from: PathBuf,
+    formats: &Formats,
+    cycles: Option<u64>,
+    ReaderOptions { feed, stop }: ReaderOptions,
+    inputs: Vec<Bits>,
+    mut devices: Devices,
+    RunOutput { vcd, trace }: RunOutput,
+) {
+    let Nodes(nodes) = read_nodes(from, formats);
+    let words: Vec<Word> = nodes
+        .iter()
+        .filter_map(|node| Word::try_from(node).ok())
+        .collect();
+
+    if words.is_empty() {
+        println!("no words to run");
+
+        return;
+    }
+
+    let cycles = cycles.unwrap_or(words.len() as u64);
+    let width = format!("{}", words.len()).len();
+    let copies = words
+        .iter()
+        .any(|word| word.ctrl().kind() == CtrlKind::CopyShift);
+    let mut reader = TapeReader::new(words).with_feed(feed).with_stop(stop);
+    let mut machine = Machine::new();
+    let mut dump = vcd.as_ref().map(|path| {
+        let file = or_exit(fs::File::create(path).map_err(io_error(path)));
+
+        or_exit(
+            Vcd::new(io::BufWriter::new(file), &machine)
+                .map_err(io_error(path)),
+        )
+    });
+
+    let mut table = new_list_table();
+    let bit = |bit: bool| if bit { "1" } else { "0" }.to_string();
+
+    match trace {
+        Some(TraceFormat::Table) => {
+            table.set_header(vec![
+                "Cycle",
+                "Word",
+                "Instruction",
+                "Address",
+                "Data",
+                "RR Before",
+                "RR After",
+                "Carry",
+                "IEN",
+                "OEN",
+                "Skipped",
+            ]);
+        }
+        Some(TraceFormat::Csv) => println!(
+            "cycle,word,instruction,address,data,rr_before,rr_after,carry,\
+             ien,oen,skipped"
+        ),
+        None => {}
+    }
+
+    for cycle in 0..cycles {
+        let index = reader.position;
+        let word = match reader.word() {
+            Some(word) => word.clone(),
+            None => break,
+        };
+
+        if let Some(Bits(bits)) = inputs.get(cycle as usize).or(inputs.last()) {
+            machine.input = *bits;
+        }
+
+        let rr = machine.rr;
+        let step = devices.step(&mut machine, &word);
+        let addr = step.word.addr();
+        let row = vec![
+            cycle.to_string(),
+            index.to_string(),
+            step.word.inst().name().to_string(),
+            match addr.symbol() {
+                Some(name) => format!("{} {}", name, addr.name()),
+                None => format!("0o{:o} {}", addr, addr.name()),
+            },
+            bit(step.data),
+            bit(rr),
+            bit(machine.rr),
+            bit(machine.carry),
+            bit(machine.ien),
+            bit(machine.oen),
+            bit(step.skipped),
+        ];
+
+        match trace {
+            Some(TraceFormat::Table) => {
+                table.add_row(row);
+            }
+            Some(TraceFormat::Csv) => println!("{}", row.join(",")),
+            None => println!(
+                "{:>8} {:>width$} {:<4} {}{}",
+                cycle,
+                index,
+                step.word.inst().name(),
+                format_bits(&machine.parallel),
+                if step.skipped { " skipped" } else { "" },
+                width = width,
+            ),
+        }
+
+        if let (Some(dump), Some(path)) = (&mut dump, &vcd) {
+            or_exit(dump.record(&step, &machine).map_err(io_error(path)));
+        }
+
+        reader.advance(&step, &machine);
+    }
+
+    if let (Some(dump), Some(path)) = (dump, &vcd) {
+        or_exit(dump.finish().map_err(io_error(path)));
+    }
+
+    if trace == Some(TraceFormat::Table) {
+        println!("{}", table);
+    }
+
+    if copies {
+        println!("shift register: {}", format_bits(&reader.shift));
+    }
+
+    if !devices.0.is_empty() {
+        let mut table = new_list_table();
+
+        table.set_header(vec!["Name", "Kind", "Addresses", "State"]);
+
+        for attached in &devices.0 {
+            let (start, end) = (attached.range.start(), attached.range.end());
+
+            table.add_row(vec![
+                attached.name.clone(),
+                attached.device.kind().to_string(),
+                if start == end {
+                    format!("0o{:02o}", start)
+                } else {
+                    format!("0o{:02o}-0o{:02o}", start, end)
+                },
+                attached.device.show(),
+            ]);
         }
-        FileType::Binary => {
-            binary::read_file(from).expect("error reading binary")
+
+        println!("{}", table);
+    }
+}
+
+fn run_debug(
+    numbers: NumberFormat,
+    symbols: bool,
+    formats: &Formats,
+    from: PathBuf,
+    ReaderOptions { feed, stop }: ReaderOptions,
+    inputs: Option<Bits>,
+) {
+    let (Nodes(nodes), info) = read_nodes_debug(from, formats);
+    let words: Vec<Word> = nodes
+        .iter()
+        .filter_map(|node| Word::try_from(node).ok())
+        .collect();
+
+    if words.is_empty() {
+        println!("no words to debug");
+
+        return;
+    }
+
+    let style = assembly::Style::default()
+        .with_radix(numbers.into())
+        .with_symbols(symbols);
+    let reader = TapeReader::new(words).with_feed(feed).with_stop(stop);
+    let mut debugger = Debugger::new(reader);
+    let mut sources: HashMap<PathBuf, Vec<String>> = HashMap::new();
+    let mut show_word = |debugger: &Debugger| {
+        let index = debugger.reader.position;
+        let word = match debugger.word() {
+            Some(word) => word.clone(),
+            None => {
+                println!("=> {} halted, `reset` rewinds the tape", index);
+
+                return;
+            }
+        };
+        let source = info
+            .as_ref()
+            .and_then(|info| info.words.get(index)?.source.clone());
+        let text = source.as_ref().and_then(|(file, line)| {
+            let lines = sources.entry(file.clone()).or_insert_with(|| {
+                fs::read_to_string(file)
+                    .map(|text| text.lines().map(String::from).collect())
+                    .unwrap_or_default()
+            });
+
+            lines
+                .get(line.checked_sub(1)?)
+                .map(|text: &String| text.trim().to_string())
+        });
+
+        match (source, text) {
+            (Some((file, line)), Some(text)) => {
+                println!("=> {} {}:{}  {}", index, file.display(), line, text)
+            }
+            _ => println!(
+                "=> {} {}",
+                index,
+                assembly::writer::format_node(&word.into(), &style)
+            ),
         }
     };
 
+    if let Some(Bits(bits)) = inputs {
+        debugger.machine.input = bits;
+    }
+
+    show_word(&debugger);
+
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+
+    loop {
+        print!("(uedbg) ");
+        or_exit(io::stdout().flush());
+
+        let line = match lines.next() {
+            Some(line) => or_exit(line),
+            None => break,
+        };
+
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let command = match Command::from_str(&line) {
+            Ok(command) => command,
+            Err(error) => {
+                println!("{}", error);
+
+                continue;
+            }
+        };
+
+        match command {
+            Command::Step(count) => {
+                for _ in 0..count {
+                    let index = debugger.reader.position;
+
+                    match debugger.step() {
+                        Some((step, stops)) => {
+                            print_debug_step(&debugger, index, &step, &stops)
+                        }
+                        None => break,
+                    }
+                }
+
+                show_word(&debugger);
+            }
+            Command::Next => {
+                let start = debugger.reader.position;
+
+                for (offset, (step, stops)) in
+                    debugger.step_over().into_iter().enumerate()
+                {
+                    let index =
+                        (start + offset) % debugger.reader.words().len();
+
+                    print_debug_step(&debugger, index, &step, &stops);
+                }
+
+                show_word(&debugger);
+            }
+            Command::Continue(limit) => {
+                for stop in debugger.resume(limit) {
+                    println!("{}", stop);
+                }
+
+                show_word(&debugger);
+            }
+            Command::Break(Breakpoint::Word(index))
+                if index >= debugger.reader.words().len() =>
+            {
+                println!(
+                    "the tape only has {} words",
+                    debugger.reader.words().len()
+                )
+            }
+            Command::Break(breakpoint) => {
+                debugger.add_breakpoint(breakpoint);
+                println!("breakpoint on {}", breakpoint);
+            }
+            Command::Watch(register) => {
+                debugger.add_watchpoint(register);
+                println!("watchpoint on {}", register);
+            }
+            Command::Delete(breakpoint) => {
+                debugger.breakpoints.retain(|other| *other != breakpoint);
+                println!("deleted breakpoint on {}", breakpoint);
+            }
+            Command::Unwatch(register) => {
+                debugger.watchpoints.retain(|other| *other != register);
+                println!("deleted watchpoint on {}", register);
+            }
+            Command::Clear => {
+                debugger.breakpoints.clear();
+                debugger.watchpoints.clear();
+                println!("deleted all breakpoints and watchpoints");
+            }
+            Command::Info
+                if debugger.breakpoints.is_empty()
+                    && debugger.watchpoints.is_empty() =>
+            {
+                println!("no breakpoints or watchpoints")
+            }
+            Command::Info => {
+                let mut table = new_list_table();
+
+                table.set_header(vec!["Kind", "On"]);
+
+                for breakpoint in &debugger.breakpoints {
+                    table.add_row(vec![
+                        "breakpoint".into(),
+                        breakpoint.to_string(),
+                    ]);
+                }
+
+                for register in &debugger.watchpoints {
+                    table.add_row(vec!["watchpoint", register.name()]);
+                }
+
+                println!("{}", table);
+            }
+            Command::Print => print_machine(&debugger),
+            Command::Input(bits) => debugger.machine.input = bits,
+            Command::Reset => {
+                debugger.reset();
+                show_word(&debugger);
+            }
+            Command::Help => println!("{}", DEBUG_HELP),
+            Command::Quit => break,
+        }
+    }
+}
+
+fn print_debug_step(
+    debugger: &Debugger,
+    index: usize,
+    step: &Step,
+    stops: &[Stop],
+) {
+    let width = format!("{}", debugger.reader.words().len()).len();
+
+    println!(
+        "{:>8} {:>width$} {:<4} {}{}",
+        debugger.machine.cycles,
+        index,
+        step.word.inst().name(),
+        format_bits(&debugger.machine.parallel),
+        if step.skipped { " skipped" } else { "" },
+        width = width,
+    );
+
+    for stop in stops {
+        println!("{}", stop);
+    }
+}
+
+fn print_machine(debugger: &Debugger) {
+    let machine = &debugger.machine;
+    let bit = |bit: bool| if bit { "1" } else { "0" }.to_string();
+    let mut table = new_list_table();
+
+    table.set_header(vec![
+        "Cycle", "Word", "RR", "Carry", "IEN", "OEN", "Skip", "Outputs",
+        "Inputs", "Shift",
+    ]);
+    table.add_row(vec![
+        machine.cycles.to_string(),
+        debugger.reader.position.to_string(),
+        bit(machine.rr),
+        bit(machine.carry),
+        bit(machine.ien),
+        bit(machine.oen),
+        bit(machine.skip),
+        format_bits(&machine.parallel),
+        format_bits(&machine.input),
+        format_bits(&debugger.reader.shift),
+    ]);
+
+    println!("{}", table);
+
+    let mut table = new_list_table();
+    let groups: Vec<_> = machine.scratch.chunks(PORT_BITS).collect();
+
+    table.set_header(
+        (0..SCRATCH_BITS)
+            .step_by(PORT_BITS)
+            .map(|bit| format!("R{}-R{}", bit, bit + PORT_BITS - 1))
+            .collect::<Vec<_>>(),
+    );
+    table.add_row(groups.into_iter().map(format_bits).collect::<Vec<_>>());
+
+    println!("{}", table);
+}
+
+fn run_list(
+    numbers: NumberFormat,
+    symbols: bool,
+    formats: &Formats,
+    from: PathBuf,
+) {
+    let (nodes, debug) = read_nodes_debug(from, formats);
+
+    print_list(numbers, symbols, nodes, debug)
+}
+
+fn print_list(
+    numbers: NumberFormat,
+    symbols: bool,
+    Nodes(nodes): Nodes,
+    debug: Option<DebugInfo>,
+) {
+    use NumberFormat::*;
+
     let mut tables = Vec::new();
     let mut table = new_list_table();
     let mut words = 0;
@@ -207,30 +1148,62 @@ fn run_list(numbers: NumberFormat, from: PathBuf) {
                 }
 
                 if words == 0 {
-                    table.set_header(vec![
-                        "#",
-                        "Instruction",
-                        "Address",
-                        "I/O Control",
-                    ]);
+                    let mut header =
+                        vec!["#", "Instruction", "Address", "I/O Control"];
+
+                    if debug.is_some() {
+                        header.push("Source");
+                    }
+
+                    table.set_header(header);
                 }
 
+                let info =
+                    debug.as_ref().and_then(|debug| debug.words.get(words));
+                let symbol = info
+                    .and_then(|info| info.symbol.clone())
+                    .or_else(|| addr.symbol());
+
                 let inst = match numbers {
                     Bin => format!("0b{:b}{:>6}", inst, inst.name()),
                     Oct => format!("0o{:o}{:>7}", inst, inst.name()),
+                    Dec => format!("0d{:02}{:>7}", inst.val(), inst.name()),
+                    Hex => format!("0x{:x}{:>8}", inst, inst.name()),
                 };
 
-                let addr = match numbers {
-                    Bin => format!("0b{:b}{:>20}", addr, addr.name()),
-                    Oct => format!("0o{:o}{:>20}", addr, addr.name()),
+                let addr = match (numbers, symbol) {
+                    (_, Some(name)) if symbols => {
+                        format!("{:<8}{:>20}", name, addr.name())
+                    }
+                    (Bin, _) => format!("0b{:b}{:>20}", addr, addr.name()),
+                    (Oct, _) => format!("0o{:o}{:>20}", addr, addr.name()),
+                    (Dec, _) => {
+                        format!("0d{:02}{:>20}", addr.val(), addr.name())
+                    }
+                    (Hex, _) => format!("0x{:x}{:>20}", addr, addr.name()),
                 };
 
                 let ctrl = match numbers {
                     Bin => format!("0b{:b}{:>20}", ctrl, ctrl.name()),
                     Oct => format!("0o{:o}{:>20}", ctrl, ctrl.name()),
+                    Dec => format!("0d{}{:>20}", ctrl.val(), ctrl.name()),
+                    Hex => format!("0x{:x}{:>20}", ctrl, ctrl.name()),
                 };
 
-                table.add_row(vec![format!("{}", words), inst, addr, ctrl]);
+                let mut row = vec![format!("{}", words), inst, addr, ctrl];
+
+                if debug.is_some() {
+                    row.push(
+                        match info.and_then(|info| info.source.as_ref()) {
+                            Some((file, line)) => {
+                                format!("{}:{}", file.display(), line)
+                            }
+                            None => String::new(),
+                        },
+                    );
+                }
+
+                table.add_row(row);
 
                 words += 1;
             }
@@ -1,10 +1,19 @@
-use std::fmt::{Binary, Display, Formatter, Octal, Result as FmtResult};
+use std::fmt::{
+    Binary, Display, Formatter, LowerHex, Octal, Result as FmtResult,
+};
 use std::ops::RangeInclusive;
 
+#[cfg(feature = "json")]
+mod repr;
+
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "json", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "json", serde(transparent))]
 pub struct Nodes(pub Vec<Node>);
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "json", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "json", serde(into = "repr::Node", from = "repr::Node"))]
 pub enum Node {
     Comment(String),
     Word(Inst, Addr, Ctrl),
@@ -19,6 +28,11 @@ impl From<Word> for Node {
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "json", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "json",
+    serde(into = "repr::Word", try_from = "repr::Word")
+)]
 pub struct Word(pub Inst, pub Addr, pub Ctrl);
 
 /// represents a 12-bit code word for the ue14500 processor
@@ -228,6 +242,8 @@ impl InstKind {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "json", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "json", serde(into = "String", try_from = "String"))]
 pub struct Inst(InstKind);
 
 impl Inst {
@@ -256,6 +272,12 @@ impl Octal for Inst {
     }
 }
 
+impl LowerHex for Inst {
+    fn fmt(&self, fmt: &mut Formatter) -> FmtResult {
+        write!(fmt, "{:01x}", self.val())
+    }
+}
+
 impl From<InstKind> for Inst {
     fn from(kind: InstKind) -> Inst {
         Inst(kind)
@@ -344,6 +366,10 @@ impl AddrKind {
     fn name(self) -> &'static str {
         ADDR_TABLE[self as usize].1
     }
+
+    fn base(self) -> u32 {
+        *ADDR_TABLE[self as usize].0.start()
+    }
 }
 
 impl Display for AddrKind {
@@ -370,6 +396,11 @@ impl Display for AddrKind {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "json", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "json",
+    serde(into = "repr::AddrValue", try_from = "repr::AddrValue")
+)]
 pub struct Addr(AddrKind, u32);
 
 impl Addr {
@@ -384,6 +415,81 @@ impl Addr {
     pub fn val(&self) -> u32 {
         self.1
     }
+
+    /// create an address from its 6-bit value, or none if the value is out of
+    /// range instead of masking it like `Addr::from` does with a whole word
+    pub fn from_val(val: u32) -> Option<Addr> {
+        (val <= ADDR_MASK >> ADDR_POS).then(|| Addr::from(val << ADDR_POS))
+    }
+
+    /// get the builtin symbolic name of the address, if it has one
+    pub fn symbol(&self) -> Option<String> {
+        let val = self.val();
+        let index = val - self.kind().base();
+
+        match self.kind() {
+            AddrKind::General => Some(format!("R{}", index)),
+            AddrKind::ParallelRead => Some(format!("PR{}", index)),
+            AddrKind::ExternalInput => Some(format!("IN{}", index)),
+            AddrKind::QRR => Some("QRR".into()),
+            AddrKind::RR => Some("RR".into()),
+            AddrKind::HighInput if index == 0 => Some("HIGH".into()),
+            AddrKind::LowInput if index == 0 => Some("LOW".into()),
+            _ => None,
+        }
+    }
+
+    /// get the address for a builtin symbolic name, ignoring case
+    pub fn from_symbol(name: &str) -> Option<Addr> {
+        let name = name.to_uppercase();
+        let indexed = |prefix: &str, kind: AddrKind| {
+            let range = &ADDR_TABLE[kind as usize].0;
+            let index = name.strip_prefix(prefix)?;
+
+            if index.is_empty() || !index.chars().all(|c| c.is_ascii_digit()) {
+                return None;
+            }
+
+            let val = kind.base() + index.parse::<u32>().ok()?;
+
+            range.contains(&val).then_some(val)
+        };
+
+        let val = match name.as_str() {
+            "QRR" => AddrKind::QRR.base(),
+            "RR" => AddrKind::RR.base(),
+            "HIGH" => AddrKind::HighInput.base(),
+            "LOW" => AddrKind::LowInput.base(),
+            _ => indexed("R", AddrKind::General)
+                .or_else(|| indexed("PR", AddrKind::ParallelRead))
+                .or_else(|| indexed("IN", AddrKind::ExternalInput))?,
+        };
+
+        Some(Addr::from(val << ADDR_POS))
+    }
+}
+
+#[test]
+fn addr_symbols() {
+    for val in 0..64 {
+        let addr = Addr::from(val << ADDR_POS);
+
+        if let Some(name) = addr.symbol() {
+            assert_eq!(Some(addr), Addr::from_symbol(&name));
+        }
+    }
+
+    assert_eq!(Some("R39".into()), Addr::from(0o47 << ADDR_POS).symbol());
+    assert_eq!(Some("IN7".into()), Addr::from(0o67 << ADDR_POS).symbol());
+    assert_eq!(None, Addr::from(0o73 << ADDR_POS).symbol());
+    assert_eq!(Some(Addr::from(0o52 << ADDR_POS)), Addr::from_symbol("pr2"));
+    assert_eq!(None, Addr::from_symbol("R40"));
+    assert_eq!(None, Addr::from_symbol("PR8"));
+    assert_eq!(None, Addr::from_symbol("R+1"));
+    assert_eq!(None, Addr::from_symbol("carry"));
+    assert_eq!(Some(Addr::from(0o77 << ADDR_POS)), Addr::from_val(63));
+    assert_eq!(None, Addr::from_val(64));
+    assert_eq!(None, Ctrl::from_val(4));
 }
 
 impl Binary for Addr {
@@ -398,6 +504,12 @@ impl Octal for Addr {
     }
 }
 
+impl LowerHex for Addr {
+    fn fmt(&self, fmt: &mut Formatter) -> FmtResult {
+        write!(fmt, "{:02x}", self.val())
+    }
+}
+
 impl From<i32> for Addr {
     fn from(word: i32) -> Addr {
         Addr::from(word as u32)
@@ -460,6 +572,11 @@ impl CtrlKind {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "json", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "json",
+    serde(into = "repr::CtrlValue", try_from = "repr::CtrlValue")
+)]
 pub struct Ctrl(CtrlKind);
 
 impl Ctrl {
@@ -474,6 +591,32 @@ impl Ctrl {
     pub fn val(&self) -> u32 {
         self.kind().val()
     }
+
+    /// create io control bits from their 2-bit value, or none if the value is
+    /// out of range
+    pub fn from_val(val: u32) -> Option<Ctrl> {
+        (val <= CTRL_MASK).then(|| Ctrl::from(val))
+    }
+
+    /// get the short symbolic name of the io control bits, if they have one
+    pub fn symbol(&self) -> Option<&'static str> {
+        match self.kind() {
+            CtrlKind::Null => Some("null"),
+            CtrlKind::CopyShift => Some("copy"),
+            CtrlKind::Undefined => None,
+            CtrlKind::StopTape => Some("stop"),
+        }
+    }
+
+    /// get the io control bits for a short symbolic name, ignoring case
+    pub fn from_symbol(name: &str) -> Option<Ctrl> {
+        match name.to_lowercase().as_str() {
+            "null" => Some(Ctrl(CtrlKind::Null)),
+            "copy" | "shift" => Some(Ctrl(CtrlKind::CopyShift)),
+            "stop" => Some(Ctrl(CtrlKind::StopTape)),
+            _ => None,
+        }
+    }
 }
 
 impl Binary for Ctrl {
@@ -488,6 +631,12 @@ impl Octal for Ctrl {
     }
 }
 
+impl LowerHex for Ctrl {
+    fn fmt(&self, fmt: &mut Formatter) -> FmtResult {
+        write!(fmt, "{:01x}", self.val())
+    }
+}
+
 impl From<CtrlKind> for Ctrl {
     fn from(kind: CtrlKind) -> Ctrl {
         Ctrl(kind)
@@ -0,0 +1,168 @@
+use super::{Addr, Ctrl, Inst, INST_TABLE};
+use serde::{Deserialize, Serialize};
+
+/// a comment or a word, tagged with which one it is
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "lowercase", deny_unknown_fields)]
+pub enum Node {
+    Comment(String),
+    Word(super::Word),
+}
+
+impl From<super::Node> for Node {
+    fn from(node: super::Node) -> Node {
+        match node {
+            super::Node::Comment(text) => Node::Comment(text),
+            super::Node::Word(inst, addr, ctrl) => {
+                Node::Word(super::Word(inst, addr, ctrl))
+            }
+        }
+    }
+}
+
+impl From<Node> for super::Node {
+    fn from(node: Node) -> super::Node {
+        match node {
+            Node::Comment(text) => super::Node::Comment(text),
+            Node::Word(word) => word.into(),
+        }
+    }
+}
+
+/// a word along with its raw 12-bit value
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Word {
+    raw: u32,
+    mnemonic: Inst,
+    addr: Addr,
+    ctrl: Ctrl,
+}
+
+impl From<super::Word> for Word {
+    fn from(word: super::Word) -> Word {
+        let super::Word(mnemonic, addr, ctrl) = word;
+
+        Word {
+            raw: u32::from(super::Word(mnemonic, addr, ctrl)),
+            mnemonic,
+            addr,
+            ctrl,
+        }
+    }
+}
+
+impl TryFrom<Word> for super::Word {
+    type Error = String;
+
+    fn try_from(word: Word) -> Result<super::Word, String> {
+        let parts = super::Word(word.mnemonic, word.addr, word.ctrl);
+
+        if word.raw >= 1 << 12 {
+            return Err(format!("raw value {} is more than 12 bits", word.raw));
+        }
+
+        if u32::from(parts.clone()) != word.raw {
+            return Err(format!(
+                "raw value {:#05x} does not match its parts",
+                word.raw
+            ));
+        }
+
+        Ok(parts)
+    }
+}
+
+impl From<Inst> for String {
+    fn from(inst: Inst) -> String {
+        inst.name().into()
+    }
+}
+
+impl TryFrom<String> for Inst {
+    type Error = String;
+
+    fn try_from(mnemonic: String) -> Result<Inst, String> {
+        INST_TABLE
+            .iter()
+            .find(|(_, name, _)| name.eq_ignore_ascii_case(&mnemonic))
+            .map(|(_, _, kind)| Inst(*kind))
+            .ok_or_else(|| format!("unknown mnemonic `{}`", mnemonic))
+    }
+}
+
+/// an address value along with the kind of address it is
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AddrValue {
+    value: u32,
+    kind: String,
+}
+
+impl From<Addr> for AddrValue {
+    fn from(addr: Addr) -> AddrValue {
+        AddrValue {
+            value: addr.val(),
+            kind: addr.name().into(),
+        }
+    }
+}
+
+impl TryFrom<AddrValue> for Addr {
+    type Error = String;
+
+    fn try_from(addr: AddrValue) -> Result<Addr, String> {
+        let parsed = Addr::from_val(addr.value).ok_or_else(|| {
+            format!("address {} is more than 6 bits", addr.value)
+        })?;
+
+        if parsed.name() != addr.kind {
+            return Err(format!(
+                "address {} is {}, not {}",
+                addr.value,
+                parsed.name(),
+                addr.kind
+            ));
+        }
+
+        Ok(parsed)
+    }
+}
+
+/// io control bits along with their name
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CtrlValue {
+    value: u32,
+    name: String,
+}
+
+impl From<Ctrl> for CtrlValue {
+    fn from(ctrl: Ctrl) -> CtrlValue {
+        CtrlValue {
+            value: ctrl.val(),
+            name: ctrl.name().into(),
+        }
+    }
+}
+
+impl TryFrom<CtrlValue> for Ctrl {
+    type Error = String;
+
+    fn try_from(ctrl: CtrlValue) -> Result<Ctrl, String> {
+        let parsed = Ctrl::from_val(ctrl.value).ok_or_else(|| {
+            format!("io control {} is more than 2 bits", ctrl.value)
+        })?;
+
+        if parsed.name() != ctrl.name {
+            return Err(format!(
+                "io control {} is {}, not {}",
+                ctrl.value,
+                parsed.name(),
+                ctrl.name
+            ));
+        }
+
+        Ok(parsed)
+    }
+}
@@ -0,0 +1,288 @@
+use crate::data::*;
+
+pub mod debugger;
+pub mod devices;
+pub mod reader;
+pub mod vcd;
+
+/// number of general purpose scratch bits, addressed from 0o00 to 0o47
+pub const SCRATCH_BITS: usize = 40;
+
+/// number of bits in the parallel read and external input ports
+pub const PORT_BITS: usize = 8;
+
+/// address of the first parallel read latch
+pub const PARALLEL_BASE: u32 = 0o50;
+
+/// address of the first external input
+pub const INPUT_BASE: u32 = 0o60;
+
+/// the registers of a ue14500 processor and the bits attached to its bus
+#[derive(Debug, Clone, PartialEq)]
+pub struct Machine {
+    /// result register
+    pub rr: bool,
+    /// carry register used by add and sub
+    pub carry: bool,
+    /// input enable latch, when clear all data reads are forced to zero
+    pub ien: bool,
+    /// output enable latch, when clear all writes are suppressed
+    pub oen: bool,
+    /// set by skz and rtn, the next word is skipped when set
+    pub skip: bool,
+    /// general purpose scratch bits
+    pub scratch: [bool; SCRATCH_BITS],
+    /// output latches that the processor can also read back in parallel
+    pub parallel: [bool; PORT_BITS],
+    /// external inputs, read only from the processor side
+    pub input: [bool; PORT_BITS],
+    /// number of clock cycles executed since reset
+    pub cycles: u64,
+}
+
+/// the observable result of executing a single word
+#[derive(Debug, Clone, PartialEq)]
+pub struct Step {
+    /// the word that was presented to the processor
+    pub word: Word,
+    /// the word was not executed because the skip flag was set
+    pub skipped: bool,
+    /// value on the data bus, either the bit read or the bit written
+    pub data: bool,
+    /// the write strobe was raised and data was stored at the address
+    pub write: bool,
+    /// flag 0 was pulsed by nop0
+    pub flag_0: bool,
+    /// flag f was pulsed by nopf
+    pub flag_f: bool,
+    /// the io control line was pulsed by ioc
+    pub ioc: bool,
+    /// the return line was pulsed by rtn
+    pub rtn: bool,
+}
+
+impl Step {
+    fn new(word: Word) -> Step {
+        Step {
+            word,
+            skipped: false,
+            data: false,
+            write: false,
+            flag_0: false,
+            flag_f: false,
+            ioc: false,
+            rtn: false,
+        }
+    }
+}
+
+impl Default for Machine {
+    fn default() -> Machine {
+        Machine {
+            rr: false,
+            carry: false,
+            ien: false,
+            oen: false,
+            skip: false,
+            scratch: [false; SCRATCH_BITS],
+            parallel: [false; PORT_BITS],
+            input: [false; PORT_BITS],
+            cycles: 0,
+        }
+    }
+}
+
+impl Machine {
+    /// create a machine in the same state as after a hardware reset
+    pub fn new() -> Machine {
+        Machine::default()
+    }
+
+    /// clear the registers and the cycle count, keeping external inputs
+    pub fn reset(&mut self) {
+        *self = Machine {
+            input: self.input,
+            ..Machine::default()
+        };
+    }
+
+    /// read the bit at an address as seen on the data bus
+    pub fn read(&self, addr: Addr) -> bool {
+        let val = addr.val();
+
+        match addr.kind() {
+            AddrKind::General => self.scratch[val as usize],
+            AddrKind::ParallelRead => {
+                self.parallel[(val - PARALLEL_BASE) as usize]
+            }
+            AddrKind::ExternalInput => self.input[(val - INPUT_BASE) as usize],
+            AddrKind::QRR => !self.rr,
+            AddrKind::RR => self.rr,
+            AddrKind::HighInput => true,
+            AddrKind::LowInput => false,
+        }
+    }
+
+    /// store a bit at an address, addresses without storage ignore it
+    pub fn write(&mut self, addr: Addr, bit: bool) {
+        let val = addr.val();
+
+        match addr.kind() {
+            AddrKind::General => self.scratch[val as usize] = bit,
+            AddrKind::ParallelRead => {
+                self.parallel[(val - PARALLEL_BASE) as usize] = bit
+            }
+            _ => {}
+        }
+    }
+
+    /// execute one word, taking exactly one clock cycle
+    pub fn step(&mut self, word: &Word) -> Step {
+        let mut step = Step::new(word.clone());
+
+        self.cycles += 1;
+
+        if self.skip {
+            self.skip = false;
+            step.skipped = true;
+
+            return step;
+        }
+
+        let addr = word.addr();
+        let bus = self.read(addr);
+        let data = bus && self.ien;
+
+        step.data = bus;
+
+        match word.inst().kind() {
+            InstKind::Nop0 => step.flag_0 = true,
+            InstKind::Ld => self.rr = data,
+            InstKind::Add => {
+                let sum = self.rr as u8 + data as u8 + self.carry as u8;
+
+                self.rr = sum & 1 == 1;
+                self.carry = sum > 1;
+            }
+            InstKind::Sub => {
+                let sum = self.rr as u8 + !data as u8 + self.carry as u8;
+
+                self.rr = sum & 1 == 1;
+                self.carry = sum > 1;
+            }
+            InstKind::One => {
+                self.rr = true;
+                self.carry = false;
+            }
+            InstKind::Nand => self.rr = !(self.rr && data),
+            InstKind::Or => self.rr = self.rr || data,
+            InstKind::Xor => self.rr ^= data,
+            InstKind::Sto | InstKind::StoC => {
+                let bit = if word.inst().kind() == InstKind::Sto {
+                    self.rr
+                } else {
+                    !self.rr
+                };
+
+                step.data = bit;
+
+                if self.oen {
+                    step.write = true;
+                    self.write(addr, bit);
+                }
+            }
+            InstKind::Ien => self.ien = bus,
+            InstKind::Oen => self.oen = bus,
+            InstKind::Ioc => step.ioc = true,
+            InstKind::Rtn => {
+                step.rtn = true;
+                self.skip = true;
+            }
+            InstKind::Skz => self.skip = !self.rr,
+            InstKind::NopF => step.flag_f = true,
+        }
+
+        step
+    }
+}
+
+#[cfg(test)]
+fn word(kind: InstKind, addr: u32) -> Word {
+    Word(
+        Inst::from(kind),
+        Addr::from(addr << ADDR_POS),
+        Ctrl::from(CtrlKind::Null),
+    )
+}
+
+#[cfg(test)]
+fn words(asm: &str) -> Vec<Word> {
+    use crate::formats::assembly;
+    use std::path::Path;
+
+    assembly::assemble(asm, Path::new("test.asm"))
+        .unwrap()
+        .0
+        .into_iter()
+        .filter_map(|node| Word::try_from(node).ok())
+        .collect()
+}
+
+#[test]
+fn step_enables() {
+    let mut machine = Machine::new();
+
+    machine.scratch[3] = true;
+    machine.step(&word(InstKind::Ld, 3));
+    assert!(!machine.rr);
+
+    machine.step(&word(InstKind::Ien, 0o72));
+    machine.step(&word(InstKind::Ld, 3));
+    assert!(machine.rr);
+
+    machine.step(&word(InstKind::Sto, 0o50));
+    assert!(!machine.parallel[0]);
+
+    machine.step(&word(InstKind::Oen, 0o71));
+    let step = machine.step(&word(InstKind::StoC, 0o51));
+    assert!(step.write);
+    assert!(!machine.parallel[1]);
+    machine.step(&word(InstKind::Sto, 0o52));
+    assert!(machine.parallel[2]);
+}
+
+#[test]
+fn step_add_sub() {
+    let mut machine = Machine::new();
+
+    machine.ien = true;
+    machine.step(&word(InstKind::One, 0));
+    machine.step(&word(InstKind::Add, 0o72));
+    assert_eq!((false, true), (machine.rr, machine.carry));
+    machine.step(&word(InstKind::Add, 0o74));
+    assert_eq!((true, false), (machine.rr, machine.carry));
+
+    // carry set means no borrow, so 1 - 1 leaves 0 and no borrow
+    machine.carry = true;
+    machine.step(&word(InstKind::Sub, 0o72));
+    assert_eq!((false, true), (machine.rr, machine.carry));
+    machine.step(&word(InstKind::Sub, 0o72));
+    assert_eq!((true, false), (machine.rr, machine.carry));
+}
+
+#[test]
+fn step_skips() {
+    let mut machine = Machine::new();
+
+    machine.step(&word(InstKind::Skz, 0));
+    assert!(machine.step(&word(InstKind::One, 0)).skipped);
+    assert!(!machine.rr);
+
+    machine.step(&word(InstKind::One, 0));
+    machine.step(&word(InstKind::Skz, 0));
+    assert!(!machine.step(&word(InstKind::Nop0, 0)).skipped);
+
+    assert!(machine.step(&word(InstKind::Rtn, 0)).rtn);
+    assert!(machine.step(&word(InstKind::Nop0, 0)).skipped);
+    assert_eq!(7, machine.cycles);
+}
@@ -0,0 +1,431 @@
+use super::reader::TapeReader;
+use super::{Machine, Step, PORT_BITS};
+use crate::data::{Addr, AddrKind, Word};
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::str::FromStr;
+
+/// number of cycles `continue` runs for when nothing stops it first
+pub const CONTINUE_LIMIT: u64 = 100_000;
+
+/// a place to stop when continuing
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Breakpoint {
+    /// stop before the word at an index is executed
+    Word(usize),
+    /// stop after a scratch bit changes value
+    Scratch(usize),
+}
+
+impl Display for Breakpoint {
+    fn fmt(&self, fmt: &mut Formatter) -> FmtResult {
+        match self {
+            Breakpoint::Word(index) => write!(fmt, "word {}", index),
+            Breakpoint::Scratch(bit) => write!(fmt, "R{}", bit),
+        }
+    }
+}
+
+impl FromStr for Breakpoint {
+    type Err = String;
+
+    /// a word index like `12`, or a scratch bit like `R3`
+    fn from_str(val: &str) -> Result<Breakpoint, String> {
+        if let Ok(index) = val.parse() {
+            return Ok(Breakpoint::Word(index));
+        }
+
+        match Addr::from_symbol(val) {
+            Some(addr) if addr.kind() == AddrKind::General => {
+                Ok(Breakpoint::Scratch(addr.val() as usize))
+            }
+            _ => Err(format!(
+                "expected a word index or scratch bit, found `{}`",
+                val
+            )),
+        }
+    }
+}
+
+/// a register that can be watched for changes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Register {
+    Rr,
+    Carry,
+    Ien,
+    Oen,
+}
+
+impl Register {
+    /// the value of the register in a machine
+    pub fn get(self, machine: &Machine) -> bool {
+        match self {
+            Register::Rr => machine.rr,
+            Register::Carry => machine.carry,
+            Register::Ien => machine.ien,
+            Register::Oen => machine.oen,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Register::Rr => "RR",
+            Register::Carry => "carry",
+            Register::Ien => "IEN",
+            Register::Oen => "OEN",
+        }
+    }
+}
+
+impl Display for Register {
+    fn fmt(&self, fmt: &mut Formatter) -> FmtResult {
+        write!(fmt, "{}", self.name())
+    }
+}
+
+impl FromStr for Register {
+    type Err = String;
+
+    fn from_str(val: &str) -> Result<Register, String> {
+        match val.to_lowercase().as_str() {
+            "rr" => Ok(Register::Rr),
+            "carry" => Ok(Register::Carry),
+            "ien" => Ok(Register::Ien),
+            "oen" => Ok(Register::Oen),
+            _ => {
+                Err(format!("expected RR, carry, IEN or OEN, found `{}`", val))
+            }
+        }
+    }
+}
+
+/// why running stopped, or something worth reporting after a step
+#[derive(Debug, Clone, PartialEq)]
+pub enum Stop {
+    /// reached a word with a breakpoint on it
+    Breakpoint(usize),
+    /// a scratch bit with a breakpoint on it changed to a value
+    Scratch(usize, bool),
+    /// a watched register changed to a value
+    Watch(Register, bool),
+    /// the tape reader halted, at a stop tape word or the end of the tape
+    Halted,
+    /// ran for the cycle limit without stopping
+    Limit(u64),
+}
+
+impl Display for Stop {
+    fn fmt(&self, fmt: &mut Formatter) -> FmtResult {
+        match self {
+            Stop::Breakpoint(index) => {
+                write!(fmt, "breakpoint at word {}", index)
+            }
+            Stop::Scratch(bit, val) => {
+                write!(fmt, "R{} changed to {}", bit, *val as u8)
+            }
+            Stop::Watch(register, val) => {
+                write!(fmt, "{} changed to {}", register, *val as u8)
+            }
+            Stop::Halted => write!(fmt, "the tape reader has halted"),
+            Stop::Limit(cycles) => {
+                write!(fmt, "still running after {} cycles", cycles)
+            }
+        }
+    }
+}
+
+/// a command typed into the debugger
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    /// execute a number of words
+    Step(u64),
+    /// execute words until one is not skipped
+    Next,
+    /// execute words until something stops it
+    Continue(u64),
+    Break(Breakpoint),
+    Watch(Register),
+    Delete(Breakpoint),
+    Unwatch(Register),
+    /// delete every breakpoint and watchpoint
+    Clear,
+    /// list breakpoints and watchpoints
+    Info,
+    /// show the machine state
+    Print,
+    /// set the external inputs
+    Input([bool; PORT_BITS]),
+    /// reset the machine and rewind the tape
+    Reset,
+    Help,
+    Quit,
+}
+
+impl FromStr for Command {
+    type Err = String;
+
+    fn from_str(val: &str) -> Result<Command, String> {
+        let mut words = val.split_whitespace();
+        let name = words.next().unwrap_or("");
+        let arg = words.next();
+
+        if let Some(extra) = words.next() {
+            return Err(format!("unexpected `{}`", extra));
+        }
+
+        let count = |default: u64| match arg {
+            Some(arg) => arg
+                .parse()
+                .map_err(|_| format!("expected a count, found `{}`", arg)),
+            None => Ok(default),
+        };
+        let required = || arg.ok_or(format!("`{}` needs an argument", name));
+
+        match name {
+            "step" | "s" => count(1).map(Command::Step),
+            "next" | "n" => Ok(Command::Next),
+            "continue" | "c" => count(CONTINUE_LIMIT).map(Command::Continue),
+            "break" | "b" => required()?.parse().map(Command::Break),
+            "watch" | "w" => required()?.parse().map(Command::Watch),
+            "delete" | "d" => match arg {
+                None => Ok(Command::Clear),
+                Some(arg) => match arg.parse() {
+                    Ok(register) => Ok(Command::Unwatch(register)),
+                    Err(_) => arg.parse().map(Command::Delete),
+                },
+            },
+            "info" | "i" => Ok(Command::Info),
+            "print" | "p" => Ok(Command::Print),
+            "input" => parse_bits(required()?).map(Command::Input),
+            "reset" => Ok(Command::Reset),
+            "help" | "h" | "?" => Ok(Command::Help),
+            "quit" | "q" => Ok(Command::Quit),
+            _ => Err(format!("unknown command `{}`, try `help`", name)),
+        }
+    }
+}
+
+fn parse_bits(val: &str) -> Result<[bool; PORT_BITS], String> {
+    let mut bits = [false; PORT_BITS];
+
+    if val.len() > PORT_BITS {
+        return Err(format!("expected at most {} bits", PORT_BITS));
+    }
+
+    for (bit, char) in bits.iter_mut().zip(val.chars()) {
+        *bit = match char {
+            '0' => false,
+            '1' => true,
+            _ => return Err("expected bits of 0 or 1".into()),
+        };
+    }
+
+    Ok(bits)
+}
+
+/// a machine running a tape in a tape reader, along with places to stop
+#[derive(Debug, Clone, PartialEq)]
+pub struct Debugger {
+    /// the reader feeding the tape, its position is the next word to execute
+    pub reader: TapeReader,
+    pub machine: Machine,
+    pub breakpoints: Vec<Breakpoint>,
+    pub watchpoints: Vec<Register>,
+}
+
+impl Debugger {
+    /// create a debugger for a tape reader with a non empty tape loaded
+    pub fn new(reader: TapeReader) -> Debugger {
+        assert!(!reader.words().is_empty(), "no words to debug");
+
+        Debugger {
+            reader,
+            machine: Machine::new(),
+            breakpoints: Vec::new(),
+            watchpoints: Vec::new(),
+        }
+    }
+
+    /// the next word to execute, or none once the reader has halted
+    pub fn word(&self) -> Option<&Word> {
+        self.reader.word()
+    }
+
+    /// reset the machine and rewind the tape, keeping external inputs
+    pub fn reset(&mut self) {
+        self.machine.reset();
+        self.reader.rewind();
+    }
+
+    /// add a breakpoint unless it is already set
+    pub fn add_breakpoint(&mut self, breakpoint: Breakpoint) {
+        if !self.breakpoints.contains(&breakpoint) {
+            self.breakpoints.push(breakpoint);
+        }
+    }
+
+    /// add a watchpoint unless it is already set
+    pub fn add_watchpoint(&mut self, register: Register) {
+        if !self.watchpoints.contains(&register) {
+            self.watchpoints.push(register);
+        }
+    }
+
+    /// execute the next word, reporting any watched values that changed and
+    /// whether the reader halted, or nothing once the reader has halted
+    pub fn step(&mut self) -> Option<(Step, Vec<Stop>)> {
+        let before = self.machine.clone();
+        let step = self.machine.step(self.reader.word()?);
+        let mut stops = Vec::new();
+
+        self.reader.advance(&step, &self.machine);
+
+        for breakpoint in &self.breakpoints {
+            if let Breakpoint::Scratch(bit) = *breakpoint {
+                let val = self.machine.scratch[bit];
+
+                if val != before.scratch[bit] {
+                    stops.push(Stop::Scratch(bit, val));
+                }
+            }
+        }
+
+        for register in &self.watchpoints {
+            let val = register.get(&self.machine);
+
+            if val != register.get(&before) {
+                stops.push(Stop::Watch(*register, val));
+            }
+        }
+
+        if self.reader.halted {
+            stops.push(Stop::Halted);
+        }
+
+        Some((step, stops))
+    }
+
+    /// execute words until one is executed rather than skipped
+    pub fn step_over(&mut self) -> Vec<(Step, Vec<Stop>)> {
+        let mut steps = Vec::new();
+
+        for _ in 0..self.reader.words().len() {
+            let (step, stops) = match self.step() {
+                Some(step) => step,
+                None => break,
+            };
+            let skipped = step.skipped;
+
+            steps.push((step, stops));
+
+            if !skipped {
+                break;
+            }
+        }
+
+        steps
+    }
+
+    /// execute words until a breakpoint, a watched value changes, the reader
+    /// halts or the cycle limit is reached
+    pub fn resume(&mut self, limit: u64) -> Vec<Stop> {
+        for _ in 0..limit {
+            let (_, mut stops) = match self.step() {
+                Some(step) => step,
+                None => return vec![Stop::Halted],
+            };
+            let index = self.reader.position;
+
+            if self.breakpoints.contains(&Breakpoint::Word(index)) {
+                stops.push(Stop::Breakpoint(index));
+            }
+
+            if !stops.is_empty() {
+                return stops;
+            }
+        }
+
+        vec![Stop::Limit(limit)]
+    }
+}
+
+#[cfg(test)]
+use super::{reader::Feed, words};
+
+#[test]
+fn debugger_breakpoints() {
+    let tape = words("IEN HIGH\nOEN HIGH\nONE RR\nSTO R3\nSTOC R3\n");
+    let mut debugger = Debugger::new(TapeReader::new(tape));
+
+    debugger.add_breakpoint("3".parse().unwrap());
+    assert_eq!(vec![Stop::Breakpoint(3)], debugger.resume(CONTINUE_LIMIT));
+    assert_eq!(3, debugger.machine.cycles);
+
+    debugger.add_breakpoint("r3".parse().unwrap());
+    assert_eq!(vec![Stop::Scratch(3, true)], debugger.resume(10));
+    assert_eq!(vec![Stop::Scratch(3, false)], debugger.resume(10));
+
+    debugger.breakpoints.clear();
+    debugger.add_watchpoint(Register::Rr);
+    assert_eq!(Vec::<Stop>::new(), debugger.step().unwrap().1);
+    assert_eq!(vec![Stop::Limit(3)], debugger.resume(3));
+    assert!("R40".parse::<Breakpoint>().is_err());
+}
+
+#[test]
+fn debugger_next() {
+    let tape = words("SKZ RR\nLD HIGH\nNOP0 RR stop\nONE RR\n");
+    let mut debugger = Debugger::new(TapeReader::new(tape));
+
+    let steps = debugger.step_over();
+
+    assert_eq!(1, steps.len());
+    assert_eq!(2, debugger.step_over().len());
+    assert_eq!(vec![Stop::Halted], debugger.resume(10));
+    assert_eq!((3, None), (debugger.reader.position, debugger.word()));
+    assert_eq!(None, debugger.step());
+    assert_eq!(vec![Stop::Halted], debugger.resume(10));
+
+    debugger.reset();
+    assert_eq!((0, 0), (debugger.reader.position, debugger.machine.cycles));
+}
+
+#[test]
+fn debugger_tape_reader() {
+    let tape = words("IEN HIGH\nONE RR copy\nNAND RR copy\nNOP0 RR stop\n");
+    let reader = TapeReader::new(tape)
+        .with_feed(Feed::Once)
+        .with_stop(false)
+        .with_shift_bits(2);
+    let mut debugger = Debugger::new(reader);
+
+    assert_eq!(vec![Stop::Halted], debugger.resume(10));
+    assert_eq!(4, debugger.machine.cycles);
+    assert_eq!(vec![false, true], debugger.reader.shift);
+
+    debugger.reset();
+    assert_eq!(vec![false, false], debugger.reader.shift);
+}
+
+#[test]
+fn parse_commands() {
+    assert_eq!(Ok(Command::Step(1)), "s".parse());
+    assert_eq!(Ok(Command::Step(5)), "step 5".parse());
+    assert_eq!(Ok(Command::Continue(CONTINUE_LIMIT)), "c".parse());
+    assert_eq!(
+        Ok(Command::Break(Breakpoint::Scratch(39))),
+        "break R39".parse()
+    );
+    assert_eq!(Ok(Command::Watch(Register::Carry)), "w carry".parse());
+    assert_eq!(Ok(Command::Unwatch(Register::Ien)), "delete IEN".parse());
+    assert_eq!(Ok(Command::Delete(Breakpoint::Word(4))), "delete 4".parse());
+    assert_eq!(Ok(Command::Clear), "d".parse());
+    assert_eq!(
+        Ok(Command::Input([
+            true, false, true, false, false, false, false, false
+        ])),
+        "input 101".parse()
+    );
+    assert!("break".parse::<Command>().is_err());
+    assert!("step 1 2".parse::<Command>().is_err());
+    assert!("jump 3".parse::<Command>().is_err());
+}
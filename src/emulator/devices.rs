@@ -0,0 +1,491 @@
+use super::{Machine, Step, INPUT_BASE, PORT_BITS};
+use crate::data::{Addr, Word};
+use std::fs::File;
+use std::io::{prelude::*, BufReader, Error, ErrorKind, Result};
+use std::ops::RangeInclusive;
+use std::path::PathBuf;
+
+/// the most bits a shift register in a device config can have
+pub const MAX_SHIFT_BITS: usize = 64;
+
+/// a simulated peripheral attached to a range of addresses on the bus
+///
+/// offsets are relative to the first address of the range the device is
+/// attached to.
+pub trait IoDevice {
+    /// short name for the kind of device
+    fn kind(&self) -> &'static str;
+
+    /// the bit the device drives onto an external input, if any
+    fn read(&mut self, _offset: u32) -> Option<bool> {
+        None
+    }
+
+    /// a bit was stored at an address while the write strobe was raised
+    fn write(&mut self, _offset: u32, _bit: bool) {}
+
+    /// the current state of the device, for showing to the user
+    fn show(&self) -> String;
+}
+
+/// a bank of switches driving external inputs
+#[derive(Debug, Clone, PartialEq)]
+pub struct Switches(pub Vec<bool>);
+
+impl IoDevice for Switches {
+    fn kind(&self) -> &'static str {
+        "switches"
+    }
+
+    fn read(&mut self, offset: u32) -> Option<bool> {
+        self.0.get(offset as usize).copied()
+    }
+
+    fn show(&self) -> String {
+        self.0
+            .iter()
+            .map(|on| if *on { '1' } else { '0' })
+            .collect()
+    }
+}
+
+/// a row of leds lit by the bits written to them
+#[derive(Debug, Clone, PartialEq)]
+pub struct Leds(pub Vec<bool>);
+
+impl IoDevice for Leds {
+    fn kind(&self) -> &'static str {
+        "leds"
+    }
+
+    fn write(&mut self, offset: u32, bit: bool) {
+        if let Some(led) = self.0.get_mut(offset as usize) {
+            *led = bit;
+        }
+    }
+
+    fn show(&self) -> String {
+        self.0
+            .iter()
+            .map(|on| if *on { '●' } else { '○' })
+            .collect()
+    }
+}
+
+/// a serial in, parallel out shift register like the 74hc595
+///
+/// the first address sets the data bit, writing a one to the second shifts
+/// it in and writing a one to the third copies the register to the outputs.
+/// without a third address the outputs follow the register.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShiftRegister {
+    pub register: Vec<bool>,
+    pub outputs: Vec<bool>,
+    data: bool,
+    clock: bool,
+    latch: bool,
+    latched: bool,
+}
+
+impl ShiftRegister {
+    /// create a cleared shift register with a number of bits
+    pub fn new(bits: usize, latched: bool) -> ShiftRegister {
+        ShiftRegister {
+            register: vec![false; bits],
+            outputs: vec![false; bits],
+            data: false,
+            clock: false,
+            latch: false,
+            latched,
+        }
+    }
+}
+
+impl IoDevice for ShiftRegister {
+    fn kind(&self) -> &'static str {
+        "shift"
+    }
+
+    fn write(&mut self, offset: u32, bit: bool) {
+        match offset {
+            0 => self.data = bit,
+            1 => {
+                if bit && !self.clock {
+                    self.register.rotate_right(1);
+                    self.register[0] = self.data;
+
+                    if !self.latched {
+                        self.outputs = self.register.clone();
+                    }
+                }
+
+                self.clock = bit;
+            }
+            2 => {
+                if bit && !self.latch {
+                    self.outputs = self.register.clone();
+                }
+
+                self.latch = bit;
+            }
+            _ => {}
+        }
+    }
+
+    fn show(&self) -> String {
+        self.outputs
+            .iter()
+            .map(|on| if *on { '1' } else { '0' })
+            .collect()
+    }
+}
+
+/// a seven segment display with a decimal point, one address for each of
+/// the segments a to g and then the point
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SevenSegment(pub [bool; 8]);
+
+/// segments lit for each hex digit, with segment a in the lowest bit
+const DIGITS: [(u8, char); 17] = [
+    (0b0111111, '0'),
+    (0b0000110, '1'),
+    (0b1011011, '2'),
+    (0b1001111, '3'),
+    (0b1100110, '4'),
+    (0b1101101, '5'),
+    (0b1111101, '6'),
+    (0b0000111, '7'),
+    (0b1111111, '8'),
+    (0b1101111, '9'),
+    (0b1110111, 'A'),
+    (0b1111100, 'b'),
+    (0b0111001, 'C'),
+    (0b1011110, 'd'),
+    (0b1111001, 'E'),
+    (0b1110001, 'F'),
+    (0b0000000, ' '),
+];
+
+impl SevenSegment {
+    /// the character shown by the lit segments, or none if they do not
+    /// make up one
+    pub fn digit(&self) -> Option<char> {
+        let segments = self.0[..7]
+            .iter()
+            .rev()
+            .fold(0, |segments, on| segments << 1 | *on as u8);
+
+        DIGITS
+            .iter()
+            .find(|(lit, _)| *lit == segments)
+            .map(|(_, digit)| *digit)
+    }
+}
+
+impl IoDevice for SevenSegment {
+    fn kind(&self) -> &'static str {
+        "segments"
+    }
+
+    fn write(&mut self, offset: u32, bit: bool) {
+        if let Some(segment) = self.0.get_mut(offset as usize) {
+            *segment = bit;
+        }
+    }
+
+    fn show(&self) -> String {
+        let point = if self.0[7] { "." } else { "" };
+        let lit: String = (b'a'..=b'g')
+            .zip(self.0)
+            .filter(|(_, on)| *on)
+            .map(|(segment, _)| segment as char)
+            .collect();
+
+        match self.digit() {
+            Some(digit) => format!("{}{} ({})", digit, point, lit),
+            None => format!("?{} ({})", point, lit),
+        }
+    }
+}
+
+/// a device along with its name and the addresses it is attached to
+pub struct Attached {
+    pub name: String,
+    pub range: RangeInclusive<u32>,
+    pub device: Box<dyn IoDevice>,
+}
+
+/// devices attached to the bus of a machine
+#[derive(Default)]
+pub struct Devices(pub Vec<Attached>);
+
+impl Devices {
+    /// attach a device to a range of addresses that no other device uses
+    pub fn attach(
+        &mut self,
+        name: &str,
+        range: RangeInclusive<u32>,
+        device: Box<dyn IoDevice>,
+    ) -> std::result::Result<(), String> {
+        if range.is_empty() || *range.end() > 0o77 {
+            return Err(format!("`{}` has an invalid address range", name));
+        }
+
+        if let Some(other) = self.0.iter().find(|other| {
+            other.range.start() <= range.end()
+                && range.start() <= other.range.end()
+        }) {
+            return Err(format!(
+                "`{}` uses the same addresses as `{}`",
+                name, other.name
+            ));
+        }
+
+        self.0.push(Attached {
+            name: name.into(),
+            range,
+            device,
+        });
+
+        Ok(())
+    }
+
+    /// execute one word with devices driving the external inputs before it
+    /// and seeing the write strobe after it
+    pub fn step(&mut self, machine: &mut Machine, word: &Word) -> Step {
+        for attached in &mut self.0 {
+            for addr in attached.range.clone() {
+                let input = addr.wrapping_sub(INPUT_BASE) as usize;
+
+                if input >= PORT_BITS {
+                    continue;
+                }
+
+                let offset = addr - attached.range.start();
+
+                if let Some(bit) = attached.device.read(offset) {
+                    machine.input[input] = bit;
+                }
+            }
+        }
+
+        let step = machine.step(word);
+
+        if step.write {
+            let addr = step.word.addr().val();
+
+            if let Some(attached) =
+                self.0.iter_mut().find(|other| other.range.contains(&addr))
+            {
+                let offset = addr - attached.range.start();
+
+                attached.device.write(offset, step.data);
+            }
+        }
+
+        step
+    }
+}
+
+/// read a device config file from disk
+pub fn read_file(path: PathBuf) -> Result<Devices> {
+    let mut buffer = File::open(path)?;
+
+    deserialize(&mut buffer)
+}
+
+/// deserialize a device config with any reader
+///
+/// each line names a kind of device, a name for it, and the first and last
+/// address it is attached to, along with the initial positions of switches or
+/// the number of bits in a shift register. `#` starts a comment.
+///
+/// ```text
+/// switches  mode    IN0-IN3  1010
+/// leds      status  PR0-PR3
+/// shift     out     R0-R2    8
+/// segments  digit   PR0-PR7
+/// ```
+pub fn deserialize(input: &mut impl Read) -> Result<Devices> {
+    let mut devices = Devices::default();
+
+    for (index, line) in BufReader::new(input).lines().enumerate() {
+        let line = line?;
+        let invalid = |message: String| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("line {}: {}", index + 1, message),
+            )
+        };
+        let fields: Vec<&str> = line
+            .split('#')
+            .next()
+            .unwrap_or("")
+            .split_whitespace()
+            .collect();
+
+        let (kind, name, range, arg) = match fields[..] {
+            [] => continue,
+            [kind, name, range] => (kind, name, range, None),
+            [kind, name, range, arg] => (kind, name, range, Some(arg)),
+            _ => {
+                return Err(invalid(
+                    "expected a kind, name, addresses and an option".into(),
+                ))
+            }
+        };
+        let range = parse_range(range).map_err(invalid)?;
+        let len = (range.end() - range.start() + 1) as usize;
+        let device: Box<dyn IoDevice> = match (kind, arg) {
+            ("switches", arg) => {
+                let mut bits = vec![false; len];
+
+                for (bit, char) in
+                    bits.iter_mut().zip(arg.unwrap_or("").chars())
+                {
+                    *bit = match char {
+                        '0' => false,
+                        '1' => true,
+                        _ => {
+                            return Err(invalid(
+                                "expected bits of 0 or 1".into(),
+                            ))
+                        }
+                    };
+                }
+
+                Box::new(Switches(bits))
+            }
+            ("leds", None) => Box::new(Leds(vec![false; len])),
+            ("shift", arg) if (2..=3).contains(&len) => {
+                let bits = match arg {
+                    Some(arg) => arg
+                        .parse()
+                        .ok()
+                        .filter(|bits| (1..=MAX_SHIFT_BITS).contains(bits))
+                        .ok_or_else(|| {
+                            invalid(format!("invalid number of bits `{}`", arg))
+                        })?,
+                    None => PORT_BITS,
+                };
+
+                Box::new(ShiftRegister::new(bits, len == 3))
+            }
+            ("shift", _) => {
+                return Err(invalid(
+                    "a shift register needs 2 or 3 addresses".into(),
+                ))
+            }
+            ("segments", None) if len <= 8 => Box::new(SevenSegment::default()),
+            ("segments", None) => {
+                return Err(invalid(
+                    "a seven segment display has at most 8 addresses".into(),
+                ))
+            }
+            ("leds" | "segments", Some(arg)) => {
+                return Err(invalid(format!("unexpected `{}`", arg)))
+            }
+            (kind, _) => {
+                return Err(invalid(format!(
+                    "unknown kind of device `{}`",
+                    kind
+                )))
+            }
+        };
+
+        devices.attach(name, range, device).map_err(invalid)?;
+    }
+
+    Ok(devices)
+}
+
+/// parse a single address or a range of them like `PR0-PR7`
+fn parse_range(val: &str) -> std::result::Result<RangeInclusive<u32>, String> {
+    let addr = |val: &str| {
+        let (digits, radix) = match val.get(..2) {
+            Some("0b") => (&val[2..], 2),
+            Some("0o") => (&val[2..], 8),
+            Some("0x") => (&val[2..], 16),
+            _ => (val, 10),
+        };
+
+        Addr::from_symbol(val)
+            .map(|addr| addr.val())
+            .or_else(|| u32::from_str_radix(digits, radix).ok())
+            .filter(|val| Addr::from_val(*val).is_some())
+            .ok_or_else(|| format!("invalid address `{}`", val))
+    };
+
+    let (first, last) = match val.split_once('-') {
+        Some((first, last)) => (addr(first)?, addr(last)?),
+        None => (addr(val)?, addr(val)?),
+    };
+
+    if first > last {
+        return Err(format!("addresses `{}` are in reverse order", val));
+    }
+
+    Ok(first..=last)
+}
+
+#[test]
+fn seven_segment_digits() {
+    let mut display = SevenSegment::default();
+
+    for (segment, on) in [true, true, false, true, true, false, true]
+        .into_iter()
+        .enumerate()
+    {
+        display.write(segment as u32, on);
+    }
+
+    assert_eq!(Some('2'), display.digit());
+
+    display.write(7, true);
+    display.write(2, true);
+
+    assert_eq!("?. (abcdeg)", display.show());
+}
+
+#[test]
+fn devices_config() {
+    use super::words;
+
+    let config = "# wiring for the test program\n\
+                  switches  mode  IN0-IN1  10\n\
+                  leds      lamp  PR0\n\
+                  shift     out   R0-R2    4   # 74hc595\n";
+    let mut devices = deserialize(&mut config.as_bytes()).unwrap();
+    let asm = "IEN HIGH\nOEN HIGH\nLD IN0\nSTO PR0\nSTO R0\nSTO R1\nSTOC R1\n\
+               LD IN1\nSTO R0\nSTOC R1\nSTO R1\nSTOC R2\n";
+    let mut machine = Machine::new();
+
+    for word in words(asm) {
+        devices.step(&mut machine, &word);
+    }
+
+    let shown: Vec<_> = devices
+        .0
+        .iter()
+        .map(|attached| (attached.name.as_str(), attached.device.show()))
+        .collect();
+
+    assert_eq!(
+        vec![
+            ("mode", "10".into()),
+            ("lamp", "●".into()),
+            ("out", "0100".into())
+        ],
+        shown
+    );
+    assert!(
+        deserialize(&mut "leds a PR0-PR3\nleds b PR3\n".as_bytes()).is_err()
+    );
+    assert!(deserialize(&mut "lamps a PR0\n".as_bytes()).is_err());
+    assert!(deserialize(&mut "shift a PR0\n".as_bytes()).is_err());
+    assert!(deserialize(&mut "leds a PR9\n".as_bytes()).is_err());
+    assert!(deserialize(&mut "leds a PR7-PR0\n".as_bytes()).is_err());
+    assert!(deserialize(&mut "shift a R0-R1 0\n".as_bytes()).is_err());
+    assert!(deserialize(&mut "shift a R0-R1 65\n".as_bytes()).is_err());
+    assert!(deserialize(&mut "shift a R0-R1 64\n".as_bytes()).is_ok());
+}
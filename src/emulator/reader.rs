@@ -0,0 +1,189 @@
+use super::{Machine, Step, PORT_BITS};
+use crate::data::{CtrlKind, Word};
+
+/// how the reader feeds a tape past its end
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Feed {
+    /// the ends of the tape are joined, so the program repeats forever
+    Loop,
+    /// the tape runs out of the reader after its last word
+    Once,
+}
+
+/// a paper tape reader feeding words to a processor
+///
+/// the io control bits punched alongside each executed word are acted on by
+/// the reader. a word with stop tape halts the reader after it, and a word
+/// with copy and shift out copies the result register into a shift register,
+/// shifting the bits already in it towards the end.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TapeReader {
+    words: Vec<Word>,
+    feed: Feed,
+    stop: bool,
+    /// index of the next word under the read head
+    pub position: usize,
+    /// the reader stopped at a stop tape word or ran off a one shot tape
+    pub halted: bool,
+    /// bits copied out with copy and shift out, most recent first
+    pub shift: Vec<bool>,
+}
+
+impl TapeReader {
+    /// load a looping tape that halts at stop tape words, with an 8 bit
+    /// shift register
+    pub fn new(words: Vec<Word>) -> TapeReader {
+        TapeReader {
+            halted: words.is_empty(),
+            words,
+            feed: Feed::Loop,
+            stop: true,
+            position: 0,
+            shift: vec![false; PORT_BITS],
+        }
+    }
+
+    /// set how the tape is fed past its end
+    pub fn with_feed(self, feed: Feed) -> TapeReader {
+        TapeReader { feed, ..self }
+    }
+
+    /// set whether stop tape words halt the reader
+    pub fn with_stop(self, stop: bool) -> TapeReader {
+        TapeReader { stop, ..self }
+    }
+
+    /// set the number of bits in the shift register, clearing it
+    pub fn with_shift_bits(self, bits: usize) -> TapeReader {
+        TapeReader {
+            shift: vec![false; bits],
+            ..self
+        }
+    }
+
+    /// the words punched on the tape
+    pub fn words(&self) -> &[Word] {
+        &self.words
+    }
+
+    /// the word under the read head, or none once the reader has halted
+    pub fn word(&self) -> Option<&Word> {
+        if self.halted {
+            return None;
+        }
+
+        self.words.get(self.position)
+    }
+
+    /// act on the io control bits of the word the processor just stepped
+    /// and move the tape on to the next word
+    pub fn advance(&mut self, step: &Step, machine: &Machine) {
+        if self.halted {
+            return;
+        }
+
+        if !step.skipped {
+            match step.word.ctrl().kind() {
+                CtrlKind::CopyShift if !self.shift.is_empty() => {
+                    self.shift.rotate_right(1);
+                    self.shift[0] = machine.rr;
+                }
+                CtrlKind::StopTape if self.stop => self.halted = true,
+                _ => {}
+            }
+        }
+
+        self.position += 1;
+
+        if self.position == self.words.len() {
+            match self.feed {
+                Feed::Loop => self.position = 0,
+                Feed::Once => self.halted = true,
+            }
+        }
+    }
+
+    /// execute the word under the read head, or nothing once halted
+    pub fn step(&mut self, machine: &mut Machine) -> Option<Step> {
+        let step = machine.step(self.word()?);
+
+        self.advance(&step, machine);
+
+        Some(step)
+    }
+
+    /// rewind the tape to its first word, clearing the shift register
+    pub fn rewind(&mut self) {
+        self.position = 0;
+        self.halted = self.words.is_empty();
+        self.shift.fill(false);
+    }
+}
+
+#[cfg(test)]
+use super::words;
+
+#[test]
+fn reader_feed() {
+    let mut machine = Machine::new();
+    let mut reader = TapeReader::new(words("ONE RR\nNAND RR\n"));
+
+    for _ in 0..5 {
+        assert!(reader.step(&mut machine).is_some());
+    }
+
+    assert_eq!(
+        (1, false, 5),
+        (reader.position, reader.halted, machine.cycles)
+    );
+
+    let mut reader =
+        TapeReader::new(words("ONE RR\nNAND RR\n")).with_feed(Feed::Once);
+
+    while reader.step(&mut machine).is_some() {}
+
+    assert_eq!(
+        (2, true, 7),
+        (reader.position, reader.halted, machine.cycles)
+    );
+
+    reader.rewind();
+
+    assert_eq!(Some(&words("ONE RR\n")[0]), reader.word());
+}
+
+#[test]
+fn reader_stop_tape() {
+    let tape = words("SKZ RR\nNOP0 RR stop\nONE RR\nNOP0 RR stop\nNAND RR\n");
+    let mut machine = Machine::new();
+    let mut reader = TapeReader::new(tape.clone());
+
+    while reader.step(&mut machine).is_some() {}
+
+    assert_eq!((4, 4), (reader.position, machine.cycles));
+
+    let mut machine = Machine::new();
+    let mut reader = TapeReader::new(tape).with_stop(false);
+
+    for _ in 0..10 {
+        reader.step(&mut machine);
+    }
+
+    assert!(!reader.halted);
+}
+
+#[test]
+fn reader_copy_shift() {
+    let tape = words(
+        "IEN HIGH\nONE RR copy\nNAND RR copy\nSKZ RR\nNOP0 RR copy\n\
+         NAND RR copy\n",
+    );
+    let mut machine = Machine::new();
+    let mut reader = TapeReader::new(tape)
+        .with_feed(Feed::Once)
+        .with_shift_bits(4);
+
+    while reader.step(&mut machine).is_some() {}
+
+    assert_eq!(vec![true, false, true, false], reader.shift);
+}
@@ -0,0 +1,191 @@
+use super::{Machine, Step, PORT_BITS};
+use std::io::{prelude::*, Result};
+
+/// name and width in bits of each signal, in the order they are dumped
+const SIGNALS: [(&str, u32); 17] = [
+    ("clk", 1),
+    ("rr", 1),
+    ("carry", 1),
+    ("ien", 1),
+    ("oen", 1),
+    ("write", 1),
+    ("addr", 6),
+    ("inst", 4),
+    ("ctrl", 2),
+    ("out0", 1),
+    ("out1", 1),
+    ("out2", 1),
+    ("out3", 1),
+    ("out4", 1),
+    ("out5", 1),
+    ("out6", 1),
+    ("out7", 1),
+];
+
+/// indexes of signals in `SIGNALS`
+const CLK: usize = 0;
+const RR: usize = 1;
+const CARRY: usize = 2;
+const IEN: usize = 3;
+const OEN: usize = 4;
+const WRITE: usize = 5;
+const ADDR: usize = 6;
+const INST: usize = 7;
+const CTRL: usize = 8;
+const OUT: usize = 9;
+
+/// writes a value change dump of a running machine, with two timesteps for
+/// each clock cycle
+///
+/// the word is put on the bus with the clock high along with the write
+/// strobe, then the registers and output latches take their new values as
+/// the clock falls.
+pub struct Vcd<W: Write> {
+    output: W,
+    time: u64,
+    /// values of the signals as last dumped
+    values: Vec<u32>,
+}
+
+impl<W: Write> Vcd<W> {
+    /// write the signal definitions and the initial state of a machine
+    pub fn new(mut output: W, machine: &Machine) -> Result<Vcd<W>> {
+        writeln!(output, "$version ue14500 emulator $end")?;
+        writeln!(output, "$timescale 1us $end")?;
+        writeln!(output, "$scope module ue14500 $end")?;
+
+        for (index, (name, width)) in SIGNALS.iter().enumerate() {
+            writeln!(
+                output,
+                "$var wire {} {} {} $end",
+                width,
+                id(index),
+                name
+            )?;
+        }
+
+        writeln!(output, "$upscope $end")?;
+        writeln!(output, "$enddefinitions $end")?;
+        writeln!(output, "#0")?;
+        writeln!(output, "$dumpvars")?;
+
+        let mut values = vec![0; SIGNALS.len()];
+
+        registers(&mut values, machine);
+
+        for (index, value) in values.iter().enumerate() {
+            writeln!(output, "{}", change(index, *value))?;
+        }
+
+        writeln!(output, "$end")?;
+
+        Ok(Vcd {
+            output,
+            time: 0,
+            values,
+        })
+    }
+
+    /// dump both phases of a step along with the machine state after it
+    pub fn record(&mut self, step: &Step, machine: &Machine) -> Result<()> {
+        let mut values = self.values.clone();
+
+        values[CLK] = 1;
+        values[WRITE] = step.write as u32;
+        values[ADDR] = step.word.addr().val();
+        values[INST] = step.word.inst().val();
+        values[CTRL] = step.word.ctrl().val();
+        self.dump(values.clone())?;
+
+        values[CLK] = 0;
+        values[WRITE] = 0;
+        registers(&mut values, machine);
+        self.dump(values)
+    }
+
+    /// write the time the last phase ends and flush the output
+    pub fn finish(mut self) -> Result<()> {
+        writeln!(self.output, "#{}", self.time + 1)?;
+
+        self.output.flush()
+    }
+
+    /// advance to the next phase and write the signals that changed
+    fn dump(&mut self, values: Vec<u32>) -> Result<()> {
+        self.time += 1;
+        writeln!(self.output, "#{}", self.time)?;
+
+        for (index, value) in values.iter().enumerate() {
+            if self.values[index] != *value {
+                writeln!(self.output, "{}", change(index, *value))?;
+            }
+        }
+
+        self.values = values;
+
+        Ok(())
+    }
+}
+
+/// copy the registers and output latches of a machine into signal values
+fn registers(values: &mut [u32], machine: &Machine) {
+    values[RR] = machine.rr as u32;
+    values[CARRY] = machine.carry as u32;
+    values[IEN] = machine.ien as u32;
+    values[OEN] = machine.oen as u32;
+
+    for bit in 0..PORT_BITS {
+        values[OUT + bit] = machine.parallel[bit] as u32;
+    }
+}
+
+/// short printable identifier of a signal
+fn id(index: usize) -> char {
+    (b'!' + index as u8) as char
+}
+
+/// a value change of a signal, scalar or vector depending on its width
+fn change(index: usize, value: u32) -> String {
+    match SIGNALS[index].1 {
+        1 => format!("{}{}", value, id(index)),
+        width => {
+            format!("b{:0width$b} {}", value, id(index), width = width as usize)
+        }
+    }
+}
+
+#[test]
+fn record_vcd() {
+    use crate::data::*;
+
+    let mut machine = Machine::new();
+    let mut vcd = Vec::new();
+    let mut writer = Vcd::new(&mut vcd, &machine).unwrap();
+
+    for bin in [0b1011_111010_00, 0b0100_111001_00, 0b1000_101001_01] {
+        let step = machine.step(&Word::from(bin));
+
+        writer.record(&step, &machine).unwrap();
+    }
+
+    writer.finish().unwrap();
+
+    let vcd = String::from_utf8(vcd).unwrap();
+    let (header, changes) = vcd.split_once("$end\n#1\n").unwrap();
+
+    assert!(header.contains("$var wire 6 ' addr $end\n"));
+    assert!(header.contains("$var wire 1 1 out7 $end\n"));
+    assert!(header.ends_with(
+        "b000000 '\nb0000 (\nb00 )\n0*\n0+\n0,\n0-\n0.\n0/\n00\n01\n"
+    ));
+    assert_eq!(
+        "1!\nb111010 '\nb1011 (\n\
+         #2\n0!\n1%\n\
+         #3\n1!\nb111001 '\nb0100 (\n\
+         #4\n0!\n1\"\n\
+         #5\n1!\n1&\nb101001 '\nb1000 (\nb01 )\n\
+         #6\n0!\n0&\n1+\n\
+         #7\n",
+        changes
+    );
+}
@@ -2,11 +2,26 @@ use std::path::PathBuf;
 
 pub mod assembly;
 pub mod binary;
+pub mod debug;
+pub mod intel_hex;
+#[cfg(feature = "json")]
+pub mod json;
+pub mod logisim;
+pub mod mem;
+pub mod srec;
+pub mod tape;
 
 #[derive(Debug)]
 pub enum FileType {
     Assembly,
     Binary,
+    IntelHex,
+    SRecord,
+    Mem,
+    Logisim,
+    Tape,
+    RawTape,
+    Json,
 }
 
 impl TryFrom<PathBuf> for FileType {
@@ -19,6 +34,23 @@ impl TryFrom<PathBuf> for FileType {
             Ok(FileType::Assembly)
         } else if str_path.ends_with(".bin") {
             Ok(FileType::Binary)
+        } else if str_path.ends_with(".hex") {
+            Ok(FileType::IntelHex)
+        } else if str_path.ends_with(".s19")
+            || str_path.ends_with(".s28")
+            || str_path.ends_with(".srec")
+        {
+            Ok(FileType::SRecord)
+        } else if str_path.ends_with(".mem") {
+            Ok(FileType::Mem)
+        } else if str_path.ends_with(".logisim") {
+            Ok(FileType::Logisim)
+        } else if str_path.ends_with(".tape") {
+            Ok(FileType::Tape)
+        } else if str_path.ends_with(".ptp") {
+            Ok(FileType::RawTape)
+        } else if str_path.ends_with(".json") {
+            Ok(FileType::Json)
         } else {
             Err(())
         }
@@ -1,19 +1,292 @@
+pub mod error;
+pub mod macros;
 pub mod parser;
+pub mod sources;
+pub mod symbols;
+pub mod writer;
 
+pub use error::{AssembleError, ReadError};
+pub use writer::{Case, Radix, Style};
+
+use super::debug::{DebugInfo, WordInfo};
 use crate::data::Nodes;
-use chonk::framework::{Parser, ParserResultMapper};
+use parser::{Operand, Spanned, Statement, SyntaxError};
+use sources::Sources;
 use std::fs::File;
 use std::io::{prelude::*, Result};
-use std::path::PathBuf;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+/// read a file from disk and assemble it into nodes
+pub fn read_file(path: PathBuf) -> std::result::Result<Nodes, ReadError> {
+    read_file_with(path, &[])
+}
 
-/// read a file from disk and deserialise words from binary
-pub fn read_file(path: PathBuf) -> Result<Nodes> {
-    let mut buffer = File::open(path).expect("error opening file for reading");
+/// read a file from disk and assemble it into nodes, searching a list of
+/// directories for included files
+pub fn read_file_with(
+    path: PathBuf,
+    include_paths: &[PathBuf],
+) -> std::result::Result<Nodes, ReadError> {
+    read_file_debug(path, include_paths).map(|(nodes, _)| nodes)
+}
+
+/// read a file from disk and assemble it into nodes, along with debug info
+/// recording where each word came from
+pub fn read_file_debug(
+    path: PathBuf,
+    include_paths: &[PathBuf],
+) -> std::result::Result<(Nodes, DebugInfo), ReadError> {
+    let io_error = |error| ReadError::Io(path.clone(), error);
+    let mut buffer = File::open(&path).map_err(io_error)?;
     let mut asm = String::new();
 
-    buffer.read_to_string(&mut asm).unwrap();
+    buffer.read_to_string(&mut asm).map_err(io_error)?;
+
+    assemble_debug(&asm, &path, include_paths).map_err(ReadError::Assemble)
+}
+
+/// assemble source text into nodes, collecting every error in the source
+pub fn assemble(
+    asm: &str,
+    file: &Path,
+) -> std::result::Result<Nodes, Vec<AssembleError>> {
+    assemble_with(asm, file, &[])
+}
+
+/// assemble source text into nodes, collecting every error in the source and
+/// in any files it includes
+///
+/// included files are found relative to the file including them first, then
+/// in each of the include paths in order.
+pub fn assemble_with(
+    asm: &str,
+    file: &Path,
+    include_paths: &[PathBuf],
+) -> std::result::Result<Nodes, Vec<AssembleError>> {
+    assemble_debug(asm, file, include_paths).map(|(nodes, _)| nodes)
+}
+
+/// assemble source text into nodes along with debug info, collecting every
+/// error in the source and in any files it includes
+pub fn assemble_debug(
+    asm: &str,
+    file: &Path,
+    include_paths: &[PathBuf],
+) -> std::result::Result<(Nodes, DebugInfo), Vec<AssembleError>> {
+    let mut sources = Sources::new(include_paths);
+    let (statements, mut errors) = sources.parse(file, asm);
+
+    match macros::expand(statements) {
+        Ok(statements) => match symbols::resolve(&statements) {
+            Ok(nodes) if errors.is_empty() => {
+                let debug =
+                    debug_info(&sources, &statements).with_comments(&nodes);
+
+                return Ok((nodes, debug));
+            }
+            Ok(_) => {}
+            Err(mut resolved) => errors.append(&mut resolved),
+        },
+        Err(mut expanded) => errors.append(&mut expanded),
+    }
+
+    errors.sort_by_key(|(span, _)| span.start);
+
+    Err(errors
+        .into_iter()
+        .map(|(span, error)| sources.error(span, error, symbol_span))
+        .collect())
+}
+
+/// record the source line of each word and the symbol it uses, leaving out
+/// symbols local to a macro expansion
+fn debug_info(sources: &Sources, statements: &[Spanned]) -> DebugInfo {
+    let words = statements
+        .iter()
+        .filter_map(|(span, statement)| match statement {
+            Statement::Word(_, operand, _) => Some(WordInfo {
+                source: sources.location(span),
+                symbol: match operand {
+                    Operand::Symbol(name) if !name.starts_with('.') => {
+                        Some(name.clone())
+                    }
+                    _ => None,
+                },
+            }),
+            _ => None,
+        })
+        .collect();
+
+    DebugInfo {
+        words,
+        ..DebugInfo::default()
+    }
+}
+
+/// narrow the span of a statement down to the symbol an error is about,
+/// redefinitions are at the start of a statement and undefined symbols are
+/// always operands further along
+fn symbol_span(text: &str, error: &SyntaxError) -> Option<Range<usize>> {
+    let (name, defined) = match error {
+        SyntaxError::UndefinedSymbol(name) => (name, false),
+        SyntaxError::Redefinition(name) => (name, true),
+        _ => return None,
+    };
+    let is_symbol_char = |c: char| c.is_ascii_alphanumeric() || c == '_';
+
+    text.match_indices(name.as_str())
+        .find(|(pos, _)| {
+            let before = text[..*pos].chars().next_back();
+            let after = text[pos + name.len()..].chars().next();
+
+            (*pos == 0) == defined
+                && !before.is_some_and(is_symbol_char)
+                && !after.is_some_and(is_symbol_char)
+        })
+        .map(|(pos, _)| pos..pos + name.len())
+}
+
+#[test]
+fn assemble_errors() {
+    let asm = "ONE 0o77\nLD 0o88\nSTO OUT\nOUT = PR0\nOUT = PR1\nLD LD\n";
+    let errors: Vec<_> = assemble(asm, Path::new("prog.asm"))
+        .unwrap_err()
+        .into_iter()
+        .map(|error| (error.line, error.column, error.span.len(), error.error))
+        .collect();
+
+    assert_eq!(
+        vec![
+            (2, 6, 2, SyntaxError::ExpectedAddr),
+            (5, 1, 3, SyntaxError::Redefinition("OUT".into())),
+            (6, 4, 2, SyntaxError::UndefinedSymbol("LD".into())),
+        ],
+        errors
+    );
+}
+
+#[test]
+fn read_missing_file() {
+    let error = read_file("missing/prog.asm".into()).unwrap_err();
+
+    assert!(error.to_string().starts_with("error: missing/prog.asm: "));
+}
+
+#[test]
+fn assemble_debug_info() {
+    let asm = "; copy\nOUT = PR0\nLD IN0\nSTO OUT\n";
+    let (nodes, debug) =
+        assemble_debug(asm, Path::new("copy.asm"), &[]).unwrap();
+    let words: Vec<_> = debug
+        .words
+        .into_iter()
+        .map(|info| (info.source.unwrap().1, info.symbol))
+        .collect();
+
+    assert_eq!(3, nodes.0.len());
+    assert_eq!(vec![(0, " copy".to_string())], debug.comments);
+    assert_eq!(vec![(3, None), (4, Some("OUT".into()))], words);
+}
+
+#[test]
+fn assemble_includes() {
+    use std::fs;
+
+    let dir = std::env::temp_dir()
+        .join(format!("ue14500-assemble-includes-{}", std::process::id()));
+    let lib = dir.join("lib");
+
+    fs::create_dir_all(&lib).unwrap();
+    fs::write(lib.join("io.asm"), "OUT = PR0\n").unwrap();
+    fs::write(
+        lib.join("copy.asm"),
+        "macro copy(a, b)\nLD a\nSTO b\nendm\n",
+    )
+    .unwrap();
+    fs::write(dir.join("loop.asm"), "include \"loop.asm\"\nLD c\n").unwrap();
+
+    let main = dir.join("main.asm");
+    let asm = "include \"lib/io.asm\"\ninclude \"copy.asm\"\ncopy(IN0, OUT)\n";
+    let nodes = assemble_with(asm, &main, &[lib]).unwrap();
+
+    assert_eq!(3, nodes.0.len());
+
+    let errors: Vec<_> = assemble("include \"loop.asm\"\n", &main)
+        .unwrap_err()
+        .into_iter()
+        .map(|error| (error.file, error.line, error.error))
+        .collect();
+
+    assert_eq!(
+        vec![
+            (
+                dir.join("loop.asm"),
+                1,
+                SyntaxError::RecursiveInclude("loop.asm".into())
+            ),
+            (
+                dir.join("loop.asm"),
+                2,
+                SyntaxError::UndefinedSymbol("c".into())
+            ),
+        ],
+        errors
+    );
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+/// serialize nodes to assembly and write a file to disk
+pub fn write_file(path: PathBuf, nodes: &Nodes, style: Style) -> Result<()> {
+    let mut buffer = File::create(path)?;
+
+    writer::serialize_with(&mut buffer, nodes, &style)
+}
+
+/// serialize nodes to assembly with any writer
+pub fn serialize(output: &mut impl Write, nodes: &Nodes) -> Result<()> {
+    writer::serialize_with(output, nodes, &Style::default())
+}
+
+#[test]
+fn serialize_round_trip() {
+    use crate::data::*;
+    use chonk::framework::{Parser, ParserResultMapper};
+
+    let nodes = Nodes(vec![
+        Node::Comment(" set all outputs".into()),
+        Node::Word(
+            Inst::from(InstKind::One),
+            Addr::from(63 << ADDR_POS),
+            Ctrl::from(CtrlKind::Null),
+        ),
+        Node::Word(
+            Inst::from(InstKind::StoC),
+            Addr::from(40 << ADDR_POS),
+            Ctrl::from(CtrlKind::StopTape),
+        ),
+        Node::Comment("".into()),
+        Node::Word(
+            Inst::from(InstKind::NopF),
+            Addr::from(0 << ADDR_POS),
+            Ctrl::from(CtrlKind::CopyShift),
+        ),
+    ]);
+
+    for radix in [Radix::Bin, Radix::Oct, Radix::Dec, Radix::Hex] {
+        for symbols in [false, true] {
+            let style = Style::default()
+                .with_radix(radix)
+                .with_case(Case::Lower)
+                .with_symbols(symbols);
+            let mut asm = Vec::new();
+
+            writer::serialize_with(&mut asm, &nodes, &style).unwrap();
 
-    let nodes = parser::nodes().parse(&asm).unwrap_result();
+            let asm = String::from_utf8(asm).unwrap();
 
-    Ok(nodes)
+            assert_eq!(nodes, parser::nodes().parse(&asm).unwrap_result());
+        }
+    }
 }
@@ -0,0 +1,127 @@
+use super::parser::SyntaxError;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::io;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+/// an error found while assembling, located in the source it came from
+#[derive(Debug, Clone, PartialEq)]
+pub struct AssembleError {
+    /// the file the error was found in
+    pub file: PathBuf,
+    /// line number of the error, starting from one
+    pub line: usize,
+    /// column number of the error in characters, starting from one
+    pub column: usize,
+    /// byte range of the offending source
+    pub span: Range<usize>,
+    /// the full line of source that contains the error
+    pub source: String,
+    /// what went wrong
+    pub error: SyntaxError,
+}
+
+impl AssembleError {
+    /// locate an error within the source of a file
+    pub fn new(
+        file: &Path,
+        source: &str,
+        span: Range<usize>,
+        error: SyntaxError,
+    ) -> AssembleError {
+        let start = span.start.min(source.len());
+        let line_start = source[..start].rfind('\n').map_or(0, |pos| pos + 1);
+        let line_end = source[start..]
+            .find('\n')
+            .map_or(source.len(), |pos| start + pos);
+
+        AssembleError {
+            file: file.into(),
+            line: source[..start].matches('\n').count() + 1,
+            column: source[line_start..start].chars().count() + 1,
+            span,
+            source: source[line_start..line_end].trim_end().into(),
+            error,
+        }
+    }
+}
+
+impl Display for AssembleError {
+    fn fmt(&self, fmt: &mut Formatter) -> FmtResult {
+        let gutter = " ".repeat(format!("{}", self.line).len());
+        let carets = self.span.len().max(1);
+
+        writeln!(fmt, "error: {}", self.error)?;
+        writeln!(
+            fmt,
+            "{}--> {}:{}:{}",
+            gutter,
+            self.file.display(),
+            self.line,
+            self.column
+        )?;
+        writeln!(fmt, "{} |", gutter)?;
+        writeln!(fmt, "{} | {}", self.line, self.source)?;
+        write!(
+            fmt,
+            "{} | {}{}",
+            gutter,
+            " ".repeat(self.column - 1),
+            "^".repeat(carets)
+        )
+    }
+}
+
+#[test]
+fn display_error() {
+    let source = "ONE RR\nLD carry\n";
+    let error = AssembleError::new(
+        Path::new("prog.asm"),
+        source,
+        10..15,
+        SyntaxError::UndefinedSymbol("carry".into()),
+    );
+
+    assert_eq!((2, 4), (error.line, error.column));
+    assert_eq!(
+        "error: undefined symbol `carry`\n \
+         --> prog.asm:2:4\n  \
+         |\n\
+         2 | LD carry\n  \
+         |    ^^^^^",
+        format!("{}", error)
+    );
+}
+
+/// an error reading assembly from disk
+#[derive(Debug)]
+pub enum ReadError {
+    /// the file could not be read
+    Io(PathBuf, io::Error),
+    /// the file was read but contains errors
+    Assemble(Vec<AssembleError>),
+}
+
+impl Display for ReadError {
+    fn fmt(&self, fmt: &mut Formatter) -> FmtResult {
+        match self {
+            ReadError::Io(path, error) => {
+                write!(fmt, "error: {}: {}", path.display(), error)
+            }
+            ReadError::Assemble(errors) => {
+                for (index, error) in errors.iter().enumerate() {
+                    if index > 0 {
+                        writeln!(fmt)?;
+                        writeln!(fmt)?;
+                    }
+
+                    write!(fmt, "{}", error)?;
+                }
+
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for ReadError {}
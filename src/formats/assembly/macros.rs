@@ -0,0 +1,325 @@
+use super::parser::{Located, Operand, Spanned, Statement, SyntaxError};
+use crate::data::Addr;
+use std::collections::HashMap;
+use std::ops::Range;
+
+/// how deep macros can call other macros before giving up
+const MAX_DEPTH: usize = 64;
+
+/// a macro definition and the statements in its body
+#[derive(Debug, Clone, PartialEq)]
+pub struct Macro {
+    pub params: Vec<String>,
+    pub body: Vec<Spanned>,
+}
+
+/// macros defined in an assembly file
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Macros(HashMap<String, Macro>);
+
+impl Macros {
+    /// get a macro by name
+    pub fn get(&self, name: &str) -> Option<&Macro> {
+        self.0.get(name)
+    }
+
+    /// define a new macro, macros can only be defined once
+    pub fn define(
+        &mut self,
+        name: &str,
+        definition: Macro,
+    ) -> Result<(), SyntaxError> {
+        if self.0.contains_key(name) {
+            return Err(SyntaxError::MacroRedefinition(name.into()));
+        }
+
+        self.0.insert(name.into(), definition);
+
+        Ok(())
+    }
+}
+
+/// collect macro definitions and expand every macro call
+///
+/// each expansion is preceded by a comment naming the call, so expansions
+/// show up in listings. symbols starting with a `.` are local to a single
+/// expansion, they are given a unique name each time the macro is used.
+pub fn expand(statements: Vec<Spanned>) -> Result<Vec<Spanned>, Vec<Located>> {
+    let mut errors = Vec::new();
+    let (macros, program) = collect(statements, &mut errors);
+    let mut expander = Expander {
+        macros,
+        errors,
+        expansions: 0,
+        stack: Vec::new(),
+    };
+    let mut output = Vec::new();
+
+    expander.expand_all(program, &mut output);
+
+    if expander.errors.is_empty() {
+        Ok(output)
+    } else {
+        expander.errors.sort_by_key(|(span, _)| span.start);
+
+        Err(expander.errors)
+    }
+}
+
+fn collect(
+    statements: Vec<Spanned>,
+    errors: &mut Vec<Located>,
+) -> (Macros, Vec<Spanned>) {
+    let mut macros = Macros::default();
+    let mut program = Vec::new();
+    let mut current: Option<(Range<usize>, String, Macro)> = None;
+
+    for (span, statement) in statements {
+        match (statement, &mut current) {
+            (Statement::Macro(name, _), Some(_)) => {
+                errors.push((span, SyntaxError::NestedMacro(name)));
+            }
+            (Statement::Macro(name, params), None) => {
+                let body = Vec::new();
+
+                // builtin names are parsed as addresses in the body, so they
+                // could never be substituted
+                for param in &params {
+                    if Addr::from_symbol(param).is_some() {
+                        let error =
+                            SyntaxError::ReservedParameter(param.into());
+
+                        errors.push((span.clone(), error));
+                    }
+                }
+
+                current = Some((span, name, Macro { params, body }));
+            }
+            (Statement::Endm, Some(_)) => {
+                if let Some((span, name, definition)) = current.take() {
+                    if let Err(error) = macros.define(&name, definition) {
+                        errors.push((span, error));
+                    }
+                }
+            }
+            (Statement::Endm, None) => {
+                errors.push((span, SyntaxError::UnexpectedEndm));
+            }
+            (statement, Some((_, _, definition))) => {
+                definition.body.push((span, statement));
+            }
+            (statement, None) => program.push((span, statement)),
+        }
+    }
+
+    if let Some((span, name, _)) = current {
+        errors.push((span, SyntaxError::UnterminatedMacro(name)));
+    }
+
+    (macros, program)
+}
+
+struct Expander {
+    macros: Macros,
+    errors: Vec<Located>,
+    expansions: usize,
+    stack: Vec<String>,
+}
+
+impl Expander {
+    fn expand_all(
+        &mut self,
+        statements: Vec<Spanned>,
+        output: &mut Vec<Spanned>,
+    ) {
+        for (span, statement) in statements {
+            match statement {
+                Statement::Call(name, args) => {
+                    self.expand_call(span, name, args, output)
+                }
+                statement => output.push((span, statement)),
+            }
+        }
+    }
+
+    fn expand_call(
+        &mut self,
+        span: Range<usize>,
+        name: String,
+        args: Vec<Operand>,
+        output: &mut Vec<Spanned>,
+    ) {
+        let definition = match self.macros.get(&name) {
+            Some(definition) => definition.clone(),
+            None => {
+                self.errors.push((span, SyntaxError::UndefinedMacro(name)));
+
+                return;
+            }
+        };
+
+        if definition.params.len() != args.len() {
+            let error = SyntaxError::MacroArguments(
+                name,
+                definition.params.len(),
+                args.len(),
+            );
+
+            self.errors.push((span, error));
+
+            return;
+        }
+
+        if self.stack.contains(&name) || self.stack.len() >= MAX_DEPTH {
+            self.errors.push((span, SyntaxError::RecursiveMacro(name)));
+
+            return;
+        }
+
+        self.expansions += 1;
+
+        let expansion = self.expansions;
+        let params: HashMap<&str, &Operand> = definition
+            .params
+            .iter()
+            .map(String::as_str)
+            .zip(args.iter())
+            .collect();
+        let substitute = |operand: Operand| match operand {
+            Operand::Symbol(symbol) => match params.get(symbol.as_str()) {
+                Some(arg) => (*arg).clone(),
+                None => Operand::Symbol(local(symbol, expansion)),
+            },
+            operand => operand,
+        };
+        let args: Vec<String> =
+            args.iter().map(|arg| arg.to_string()).collect();
+        let indent = "  ".repeat(self.stack.len());
+        let mut body = Vec::new();
+
+        output.push((
+            span,
+            Statement::Comment(format!(
+                " {}{}({})",
+                indent,
+                name,
+                args.join(", ")
+            )),
+        ));
+
+        for (span, statement) in definition.body {
+            let statement = match statement {
+                Statement::Define(symbol, operand) => Statement::Define(
+                    local(symbol, expansion),
+                    substitute(operand),
+                ),
+                Statement::Word(inst, operand, ctrl) => {
+                    Statement::Word(inst, substitute(operand), ctrl)
+                }
+                Statement::Call(name, args) => Statement::Call(
+                    name,
+                    args.into_iter().map(substitute).collect(),
+                ),
+                statement => statement,
+            };
+
+            body.push((span, statement));
+        }
+
+        self.stack.push(name);
+        self.expand_all(body, output);
+        self.stack.pop();
+    }
+}
+
+/// give local symbols a name that is unique to a single expansion
+fn local(symbol: String, expansion: usize) -> String {
+    if symbol.starts_with('.') {
+        format!("{}.{}", symbol, expansion)
+    } else {
+        symbol
+    }
+}
+
+#[test]
+fn expand_macros() {
+    use super::parser::statements;
+    use super::symbols::resolve;
+    use crate::data::*;
+    use chonk::prelude::*;
+
+    let asm = r"
+    macro copy(from, into)
+        LD from
+        STO into
+    endm
+
+    macro swap(a, b)
+        .t = R39
+        copy(a, .t)
+        copy(b, a)
+        copy(.t, b)
+    endm
+
+    swap(R1, R2)
+    swap(R3, R4)
+    ";
+
+    let statements = statements().parse(asm).unwrap_result();
+    let Nodes(nodes) = resolve(&expand(statements).unwrap()).unwrap();
+    let comments: Vec<_> = nodes
+        .iter()
+        .filter_map(|node| match node {
+            Node::Comment(text) => Some(text.as_str()),
+            _ => None,
+        })
+        .collect();
+    let addrs: Vec<_> = nodes
+        .iter()
+        .filter_map(|node| Word::try_from(node).ok())
+        .map(|word| word.addr().val())
+        .collect();
+
+    assert_eq!(
+        vec![
+            " swap(R1, R2)",
+            "   copy(R1, .t.1)",
+            "   copy(R2, R1)",
+            "   copy(.t.1, R2)",
+            " swap(R3, R4)",
+            "   copy(R3, .t.5)",
+            "   copy(R4, R3)",
+            "   copy(.t.5, R4)",
+        ],
+        comments
+    );
+    assert_eq!(vec![1, 39, 2, 1, 39, 2, 3, 39, 4, 3, 39, 4], addrs);
+}
+
+#[test]
+fn expand_errors() {
+    use super::parser::statements;
+    use chonk::prelude::*;
+
+    let asm = "macro a(x)\nb(x)\nendm\nmacro b(y)\na(y)\nendm\na(R1)\nc()\n\
+               a(R1, R2)\nendm\nmacro e(rr, x, High)\nendm\nmacro d()\n";
+    let statements = statements().parse(asm).unwrap_result();
+    let errors: Vec<_> = expand(statements)
+        .unwrap_err()
+        .into_iter()
+        .map(|(_, error)| error)
+        .collect();
+
+    assert_eq!(
+        vec![
+            SyntaxError::RecursiveMacro("a".into()),
+            SyntaxError::UndefinedMacro("c".into()),
+            SyntaxError::MacroArguments("a".into(), 1, 2),
+            SyntaxError::UnexpectedEndm,
+            SyntaxError::ReservedParameter("rr".into()),
+            SyntaxError::ReservedParameter("High".into()),
+            SyntaxError::UnterminatedMacro("d".into()),
+        ],
+        errors
+    );
+}
@@ -1,23 +1,302 @@
+use super::{macros, symbols};
 use crate::data::*;
 use chonk::prelude::*;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::ops::Range;
 use SyntaxError::*;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum SyntaxError {
     ExpectedInst(Inst),
     ExpectedAddr,
     ExpectedCtrl,
     ExpectedWord,
     ExpectedComment,
+    ExpectedSymbol,
+    ExpectedDefinition,
+    ExpectedNewline,
+    ExpectedMacro,
+    ExpectedCall,
+    ExpectedInclude,
+    AddrRange(String),
+    CtrlRange(String),
+    UndefinedSymbol(String),
+    Redefinition(String),
+    UndefinedMacro(String),
+    MacroRedefinition(String),
+    MacroArguments(String, usize, usize),
+    ReservedParameter(String),
+    RecursiveMacro(String),
+    NestedMacro(String),
+    UnterminatedMacro(String),
+    UnexpectedEndm,
+    IncludeFailed(String, String),
+    RecursiveInclude(String),
+    UnresolvedInclude(String),
     UnexpectedEoi,
 }
 
-#[allow(clippy::redundant_closure)]
+impl Display for SyntaxError {
+    fn fmt(&self, fmt: &mut Formatter) -> FmtResult {
+        match self {
+            ExpectedInst(_) => write!(fmt, "expected an instruction"),
+            ExpectedAddr => write!(fmt, "expected an address"),
+            ExpectedCtrl => write!(fmt, "expected io control bits"),
+            ExpectedWord => write!(fmt, "expected a word"),
+            ExpectedComment => write!(fmt, "expected a comment"),
+            ExpectedSymbol => write!(fmt, "expected a symbol"),
+            ExpectedDefinition => write!(fmt, "expected a symbol definition"),
+            ExpectedNewline => write!(fmt, "expected the end of the line"),
+            AddrRange(literal) => {
+                write!(fmt, "address `{}` is out of range 0 to 63", literal)
+            }
+            CtrlRange(literal) => {
+                write!(fmt, "io control `{}` is out of range 0 to 3", literal)
+            }
+            UndefinedSymbol(name) => write!(fmt, "undefined symbol `{}`", name),
+            Redefinition(name) => {
+                write!(fmt, "symbol `{}` is already defined", name)
+            }
+            ExpectedMacro => write!(fmt, "expected a macro definition"),
+            ExpectedCall => write!(fmt, "expected a macro call"),
+            ExpectedInclude => write!(fmt, "expected an include directive"),
+            UndefinedMacro(name) => write!(fmt, "undefined macro `{}`", name),
+            MacroRedefinition(name) => {
+                write!(fmt, "macro `{}` is already defined", name)
+            }
+            MacroArguments(name, expected, found) => write!(
+                fmt,
+                "macro `{}` takes {} arguments but {} were given",
+                name, expected, found
+            ),
+            ReservedParameter(name) => write!(
+                fmt,
+                "macro parameter `{}` is a builtin address name",
+                name
+            ),
+            RecursiveMacro(name) => {
+                write!(fmt, "macro `{}` expands into itself", name)
+            }
+            NestedMacro(name) => {
+                write!(fmt, "macro `{}` is defined inside another macro", name)
+            }
+            UnterminatedMacro(name) => {
+                write!(fmt, "macro `{}` is missing `endm`", name)
+            }
+            UnexpectedEndm => write!(fmt, "`endm` outside of a macro"),
+            IncludeFailed(path, reason) => {
+                write!(fmt, "could not include `{}`: {}", path, reason)
+            }
+            RecursiveInclude(path) => {
+                write!(fmt, "`{}` includes itself", path)
+            }
+            UnresolvedInclude(path) => write!(
+                fmt,
+                "`{}` can only be included when assembling a file",
+                path
+            ),
+            UnexpectedEoi => write!(fmt, "unexpected end of input"),
+        }
+    }
+}
+
+/// an address operand, either a literal or the name of a symbol
+#[derive(Debug, Clone, PartialEq)]
+pub enum Operand {
+    Addr(Addr),
+    Symbol(String),
+}
+
+impl Display for Operand {
+    fn fmt(&self, fmt: &mut Formatter) -> FmtResult {
+        match self {
+            Operand::Addr(addr) => match addr.symbol() {
+                Some(name) => write!(fmt, "{}", name),
+                None => write!(fmt, "0o{:o}", addr),
+            },
+            Operand::Symbol(name) => write!(fmt, "{}", name),
+        }
+    }
+}
+
+/// a single line of assembly before files are included, macros are expanded
+/// and symbols are resolved
+#[derive(Debug, Clone, PartialEq)]
+pub enum Statement {
+    Comment(String),
+    Define(String, Operand),
+    Word(Inst, Operand, Ctrl),
+    Macro(String, Vec<String>),
+    Endm,
+    Call(String, Vec<Operand>),
+    Include(String),
+}
+
+/// a statement along with the byte range it was parsed from
+pub type Spanned = (Range<usize>, Statement);
+
+/// parse assembly and resolve symbols into nodes
 pub fn nodes<'a>() -> impl Parser<'a, Nodes, SyntaxError> {
     move |ctx| {
-        trim(find_until(eoi(), trim(find_any((comment(), word())))))
-            .parse(ctx)
-            .map_result(|nodes| Nodes(nodes))
+        let (ctx, statements) = statements().parse(ctx)?;
+        let statements = match macros::expand(statements) {
+            Ok(statements) => statements,
+            Err(errors) => {
+                let errors = errors
+                    .into_iter()
+                    .map(|(span, error)| {
+                        ParserError::Range(span).with_message(error)
+                    })
+                    .collect();
+
+                return Err((ctx, ParserError::Stack(errors)));
+            }
+        };
+
+        match symbols::resolve(&statements) {
+            Ok(nodes) => Ok((ctx, nodes)),
+            Err(errors) => {
+                let errors = errors
+                    .into_iter()
+                    .map(|(span, error)| {
+                        ParserError::Range(span).with_message(error)
+                    })
+                    .collect();
+
+                Err((ctx, ParserError::Stack(errors)))
+            }
+        }
+    }
+}
+
+/// parse assembly into statements without resolving symbols
+pub fn statements<'a>() -> impl Parser<'a, Vec<Spanned>, SyntaxError> {
+    move |ctx| trim(find_until(eoi(), trim(spanned(statement())))).parse(ctx)
+}
+
+/// parse assembly into statements, skipping to the next line after an error
+/// so that every syntax error in the input can be reported
+pub fn statements_recovering<'a>(
+) -> impl Parser<'a, Vec<Result<Spanned, Located>>, SyntaxError> {
+    move |ctx| {
+        trim(find_until(eoi(), trim(recover(spanned(statement()))))).parse(ctx)
+    }
+}
+
+/// a syntax error along with the byte range it applies to
+pub type Located = (Range<usize>, SyntaxError);
+
+fn recover<'a, Res>(
+    mut parser: impl Parser<'a, Res, SyntaxError>,
+) -> impl Parser<'a, Result<Res, Located>, SyntaxError> {
+    move |ctx: ParserContext<'a>| match parser.parse(ctx.clone()) {
+        Ok((ctx, res)) => Ok((ctx, Ok(res))),
+        Err((_, error)) => {
+            let (ctx, _) =
+                take_all((take_until(newline(), is(any)), newline()))
+                    .parse(ctx)?;
+
+            Ok((ctx.clone(), Err(locate(ctx.input, &error))))
+        }
+    }
+}
+
+/// find the furthest point a parser reached before failing, and the most
+/// specific message that was attached to the failure at that point
+pub fn locate(input: &str, error: &ParserError<SyntaxError>) -> Located {
+    fn furthest<'e>(
+        error: &'e ParserError<SyntaxError>,
+        message: Option<&'e SyntaxError>,
+        found: &mut Option<(usize, Option<&'e SyntaxError>)>,
+    ) {
+        match error {
+            ParserError::Range(bounds) => match found {
+                Some((end, _)) if *end > bounds.end => {}
+                // a number out of range is more specific than anything else
+                // that failed at the same point
+                Some((end, Some(AddrRange(_) | CtrlRange(_))))
+                    if *end == bounds.end => {}
+                _ => *found = Some((bounds.end, message)),
+            },
+            ParserError::Stack(errors) => {
+                for error in errors {
+                    furthest(error, message, found);
+                }
+            }
+            ParserError::Message(message, error) => {
+                furthest(error, Some(message), found)
+            }
+        }
+    }
+
+    let mut found = None;
+
+    furthest(error, None, &mut found);
+
+    let (start, message) = found.unwrap_or((0, None));
+    let start = start.min(input.len());
+    let length = input[start..]
+        .find(char::is_whitespace)
+        .unwrap_or(input.len() - start);
+    let error = message.cloned().unwrap_or(ExpectedWord);
+
+    (start..start + length, error)
+}
+
+#[test]
+fn parse_statements_recovering() {
+    let asm = "ONE 0o77\nLD 0o88\nSTO PR0\nOEN HIGH zz\n";
+    let statements = statements_recovering().parse(asm).unwrap_result();
+    let errors: Vec<_> =
+        statements.into_iter().filter_map(|res| res.err()).collect();
+
+    assert_eq!(
+        vec![(14..16, ExpectedAddr), (34..36, ExpectedNewline)],
+        errors
+    );
+}
+
+#[test]
+fn parse_range_errors() {
+    let asm = "LD 0o777\nSTO 64 0\nNOP0 1_0 7\nSTO 0x3F 0d3\n";
+    let statements = statements_recovering().parse(asm).unwrap_result();
+    let errors: Vec<_> =
+        statements.into_iter().filter_map(|res| res.err()).collect();
+
+    assert_eq!(
+        vec![
+            (3..8, AddrRange("0o777".into())),
+            (13..15, AddrRange("64".into())),
+            (27..28, CtrlRange("7".into())),
+        ],
+        errors
+    );
+}
+
+fn statement<'a>() -> impl Parser<'a, Statement, SyntaxError> {
+    move |ctx| {
+        find_any((
+            comment(),
+            include(),
+            macro_def(),
+            endm(),
+            define(),
+            word(),
+            call(),
+        ))
+        .parse(ctx)
+    }
+}
+
+fn spanned<'a, Res>(
+    mut parser: impl Parser<'a, Res, SyntaxError>,
+) -> impl Parser<'a, (Range<usize>, Res), SyntaxError> {
+    move |ctx| {
+        parser.parse(ctx).map(|(ctx, res)| {
+            let bounds = ctx.bounds.clone();
+
+            (ctx, (bounds, res))
+        })
     }
 }
 
@@ -74,42 +353,112 @@ fn parse_nodes() {
     assert_eq!(expected, nodes().parse(asm).unwrap_result());
 }
 
-fn bin<'a>() -> impl Parser<'a, u32, SyntaxError> {
+/// parse a number literal with a radix prefix like `0b`, `0o`, `0x` or `0d`,
+/// or a plain decimal number, allowing `_` between digits
+fn number<'a>() -> impl Parser<'a, u32, SyntaxError> {
     move |ctx| {
-        find_all((is("0b"), take(1..32, is(one_of("01")))))
-            .parse(ctx)
-            .map_result(|(_, bin)| u32::from_str_radix(bin, 2).unwrap())
-            .map_error(|err| err.with_message(ExpectedAddr))
+        find_any((
+            prefixed("0b", 2),
+            prefixed("0o", 8),
+            prefixed("0x", 16),
+            prefixed("0h", 16),
+            prefixed("0d", 10),
+            decimal(),
+        ))
+        .parse(ctx)
+        .map_error(|err| err.with_message(ExpectedAddr))
     }
 }
 
-fn oct<'a>() -> impl Parser<'a, u32, SyntaxError> {
+fn prefixed<'a>(
+    prefix: &'static str,
+    radix: u32,
+) -> impl Parser<'a, u32, SyntaxError> {
     move |ctx| {
-        find_all((is("0o"), take(1..11, is(one_of("01234567")))))
+        find_all((is(prefix), digits(radix)))
             .parse(ctx)
-            .map_result(|(_, octal)| u32::from_str_radix(octal, 8).unwrap())
-            .map_error(|err| err.with_message(ExpectedAddr))
+            .map_result(|(_, val)| val)
+    }
+}
+
+fn decimal<'a>() -> impl Parser<'a, u32, SyntaxError> {
+    move |ctx: ParserContext<'a>| {
+        let error = ParserError::Range(ctx.bounds.clone());
+
+        if !ctx.unparsed().starts_with(|c: char| c.is_ascii_digit()) {
+            return Err((ctx, error));
+        }
+
+        let (ret_ctx, val) = digits(10).parse(ctx.clone())?;
+
+        // a number running into letters is neither a number nor a symbol
+        if ret_ctx.unparsed().starts_with(symbol_char) {
+            return Err((ctx, error));
+        }
+
+        Ok((ret_ctx, val))
     }
 }
 
-fn hex<'a>() -> impl Parser<'a, u32, SyntaxError> {
+/// parse digits in a radix along with any `_` separators, values too large
+/// for a u32 saturate so that they are caught by range checks
+fn digits<'a>(radix: u32) -> impl Parser<'a, u32, SyntaxError> {
+    move |ctx: ParserContext<'a>| {
+        let (ret_ctx, digits) =
+            take(1.., is(move |c: char| c == '_' || c.is_digit(radix)))
+                .parse(ctx.clone())?;
+        let digits = digits.replace('_', "");
+
+        if digits.is_empty() {
+            let error = ParserError::Range(ctx.bounds.clone());
+
+            return Err((ctx, error));
+        }
+
+        Ok((
+            ret_ctx,
+            u32::from_str_radix(&digits, radix).unwrap_or(u32::MAX),
+        ))
+    }
+}
+
+#[test]
+fn parse_number() {
+    assert_eq!(5, number().parse("0b101").unwrap_result());
+    assert_eq!(0o57, number().parse("0o57").unwrap_result());
+    assert_eq!(0x2f, number().parse("0x2F").unwrap_result());
+    assert_eq!(0x2f, number().parse("0h2f").unwrap_result());
+    assert_eq!(47, number().parse("0d47").unwrap_result());
+    assert_eq!(47, number().parse("47").unwrap_result());
+    assert_eq!(0b11_0001, number().parse("0b11_0001").unwrap_result());
+    assert_eq!(1_000, number().parse("1_000").unwrap_result());
+    assert_eq!(u32::MAX, number().parse("99999999999").unwrap_result());
+    assert!(number().parse("0x_").is_err());
+    assert!(number().parse("_1").is_err());
+    assert!(number().parse("12ab").is_err());
+}
+
+fn newline<'a>() -> impl Parser<'a, &'a str, SyntaxError> {
     move |ctx| {
-        find_all((is("0h"), take(1..8, is(hex_digit))))
+        take_any((eoi(), take_any((is("\n"), is("\r\n")))))
             .parse(ctx)
-            .map_result(|(_, hex)| u32::from_str_radix(hex, 16).unwrap())
-            .map_error(|err| err.with_message(ExpectedAddr))
+            .map_error(|err| err.with_message(ExpectedNewline))
     }
 }
 
-fn newline<'a>() -> impl Parser<'a, &'a str, SyntaxError> {
-    move |ctx| take_any((eoi(), take_any((is("\n"), is("\r\n"))))).parse(ctx)
+fn blank<'a>(
+    range: impl std::ops::RangeBounds<usize> + Clone,
+) -> impl Parser<'a, &'a str, SyntaxError> {
+    move |ctx| take(range.clone(), is(one_of(" \t"))).parse(ctx)
 }
 
-fn comment<'a>() -> impl Parser<'a, Node, SyntaxError> {
+fn comment<'a>() -> impl Parser<'a, Statement, SyntaxError> {
     move |ctx| {
         find_all((is(';'), take_until(newline(), is(any)), newline()))
             .parse(ctx)
-            .map_result(|(_, text, ..)| Node::Comment(text.trim_end().into()))
+            .map_result(|(_, text, ..)| {
+                Statement::Comment(text.trim_end().into())
+            })
             .map_error(|err| err.with_message(ExpectedComment))
     }
 }
@@ -117,39 +466,239 @@ fn comment<'a>() -> impl Parser<'a, Node, SyntaxError> {
 #[test]
 fn parse_comment() {
     assert_eq!(
-        Node::Comment("ONE 0o77 00".into()),
+        Statement::Comment("ONE 0o77 00".into()),
         comment().parse(";ONE 0o77 00").unwrap_result()
     );
     assert_eq!(
-        Node::Comment("".into()),
+        Statement::Comment("".into()),
         comment().parse(";   \n").unwrap_result()
     );
     assert_eq!(
-        Node::Comment(" foo bar".into()),
+        Statement::Comment(" foo bar".into()),
         comment().parse("; foo bar  \n").unwrap_result()
     );
 }
 
-fn word<'a>() -> impl Parser<'a, Node, SyntaxError> {
+fn define<'a>() -> impl Parser<'a, Statement, SyntaxError> {
     move |ctx| {
-        find_all((inst(), space(1..), addr(), space(1..), ctrl(), newline()))
+        find_all((
+            symbol(),
+            blank(0..),
+            is('='),
+            blank(0..),
+            operand(),
+            blank(0..),
+            newline(),
+        ))
+        .parse(ctx)
+        .map_result(|(name, _, _, _, operand, ..)| {
+            Statement::Define(name.into(), operand)
+        })
+        .map_error(|err| err.with_message(ExpectedDefinition))
+    }
+}
+
+#[test]
+fn parse_define() {
+    assert_eq!(
+        Statement::Define(
+            "carry".into(),
+            Operand::Addr(Addr::from(10 << ADDR_POS))
+        ),
+        define().parse("carry = 0o12").unwrap_result()
+    );
+    assert_eq!(
+        Statement::Define("OUT0".into(), Operand::Symbol("out_a".into())),
+        define().parse("OUT0=out_a \n").unwrap_result()
+    );
+    assert!(define().parse("ONE 0o77 0b0").is_err());
+}
+
+fn keyword<'a>(name: &'static str) -> impl Parser<'a, &'a str, SyntaxError> {
+    move |ctx| take_any((is(name), is(name.to_uppercase()))).parse(ctx)
+}
+
+fn include<'a>() -> impl Parser<'a, Statement, SyntaxError> {
+    move |ctx| {
+        find_all((
+            keyword("include"),
+            blank(1..),
+            is('"'),
+            take(1.., is(|c: char| c != '"' && c != '\n')),
+            is('"'),
+            blank(0..),
+            newline(),
+        ))
+        .parse(ctx)
+        .map_result(|(_, _, _, path, ..)| Statement::Include(path.into()))
+        .map_error(|err| err.with_message(ExpectedInclude))
+    }
+}
+
+#[test]
+fn parse_include() {
+    assert_eq!(
+        Statement::Include("lib/io.asm".into()),
+        include().parse("include \"lib/io.asm\"\n").unwrap_result()
+    );
+    assert_eq!(
+        Statement::Include("macros.asm".into()),
+        include().parse("INCLUDE \"macros.asm\"").unwrap_result()
+    );
+    assert!(include().parse("include macros.asm").is_err());
+    assert!(include().parse("include \"\"").is_err());
+    assert!(nodes().parse("include \"macros.asm\"\n").is_err());
+}
+
+fn macro_def<'a>() -> impl Parser<'a, Statement, SyntaxError> {
+    move |ctx| {
+        find_all((
+            keyword("macro"),
+            blank(1..),
+            symbol(),
+            blank(0..),
+            is('('),
+            find_separated(
+                0..,
+                move |ctx| {
+                    find_all((blank(0..), symbol(), blank(0..)))
+                        .parse(ctx)
+                        .map_result(|(_, param, _)| param.to_string())
+                },
+                is(','),
+            ),
+            is(')'),
+            blank(0..),
+            newline(),
+        ))
+        .parse(ctx)
+        .map_result(|(_, _, name, _, _, params, ..)| {
+            Statement::Macro(name.into(), params)
+        })
+        .map_error(|err| err.with_message(ExpectedMacro))
+    }
+}
+
+fn endm<'a>() -> impl Parser<'a, Statement, SyntaxError> {
+    move |ctx| {
+        find_all((keyword("endm"), blank(0..), newline()))
             .parse(ctx)
-            .map_result(|(inst, _, addr, _, ctrl, ..)| {
-                Node::Word(inst, addr, ctrl)
-            })
+            .map_result(|_| Statement::Endm)
+    }
+}
+
+fn call<'a>() -> impl Parser<'a, Statement, SyntaxError> {
+    move |ctx| {
+        find_all((
+            symbol(),
+            blank(0..),
+            is('('),
+            find_separated(
+                0..,
+                move |ctx| {
+                    find_all((blank(0..), operand(), blank(0..)))
+                        .parse(ctx)
+                        .map_result(|(_, arg, _)| arg)
+                },
+                is(','),
+            ),
+            is(')'),
+            blank(0..),
+            newline(),
+        ))
+        .parse(ctx)
+        .map_result(|(name, _, _, args, ..)| Statement::Call(name.into(), args))
+        .map_error(|err| err.with_message(ExpectedCall))
+    }
+}
+
+#[test]
+fn parse_macro() {
+    assert_eq!(
+        Statement::Macro("xor3".into(), vec!["a".into(), "b".into()]),
+        macro_def().parse("macro xor3( a, b )\n").unwrap_result()
+    );
+    assert_eq!(
+        Statement::Macro("halt".into(), vec![]),
+        macro_def().parse("MACRO halt()").unwrap_result()
+    );
+    assert_eq!(Statement::Endm, endm().parse("endm  \n").unwrap_result());
+    assert_eq!(
+        Statement::Call(
+            "copy".into(),
+            vec![
+                Operand::Addr(Addr::from(0o60 << ADDR_POS)),
+                Operand::Symbol(".out".into()),
+            ]
+        ),
+        call().parse("copy(IN0, .out)").unwrap_result()
+    );
+    assert!(call().parse("copy IN0, PR0").is_err());
+}
+
+fn word<'a>() -> impl Parser<'a, Statement, SyntaxError> {
+    move |ctx| {
+        find_all((
+            inst(),
+            blank(1..),
+            operand(),
+            // not optional, so that io control bits out of range are
+            // reported rather than a missing newline
+            find_any((
+                move |ctx| {
+                    find_all((blank(1..), ctrl(), blank(0..), newline()))
+                        .parse(ctx)
+                        .map_result(|(_, ctrl, ..)| ctrl)
+                },
+                move |ctx| {
+                    find_all((blank(0..), newline()))
+                        .parse(ctx)
+                        .map_result(|_| Ctrl::from(CtrlKind::Null))
+                },
+            )),
+        ))
+        .parse(ctx)
+        .map_result(|(inst, _, operand, ctrl)| {
+            Statement::Word(inst, operand, ctrl)
+        })
     }
 }
 
 #[test]
 fn parse_word() {
     assert_eq!(
-        Node::Word(
+        Statement::Word(
             Inst::from(InstKind::One),
-            Addr::from(63 << ADDR_POS),
+            Operand::Addr(Addr::from(63 << ADDR_POS)),
             Ctrl::from(CtrlKind::Null)
         ),
         word().parse("ONE 0o77 0h0").unwrap_result()
     );
+    assert_eq!(
+        Statement::Word(
+            Inst::from(InstKind::Sto),
+            Operand::Symbol("OUT0".into()),
+            Ctrl::from(CtrlKind::Null)
+        ),
+        word().parse("STO\tOUT0  0b0  \n").unwrap_result()
+    );
+    assert_eq!(
+        Statement::Word(
+            Inst::from(InstKind::Ld),
+            Operand::Addr(Addr::from(0o60 << ADDR_POS)),
+            Ctrl::from(CtrlKind::Null)
+        ),
+        word().parse("LD IN0\nSTO PR0").unwrap_result()
+    );
+    assert_eq!(
+        Statement::Word(
+            Inst::from(InstKind::Nop0),
+            Operand::Addr(Addr::from(0 << ADDR_POS)),
+            Ctrl::from(CtrlKind::StopTape)
+        ),
+        word().parse("NOP0 0o0 stop").unwrap_result()
+    );
+    assert!(word().parse("NOP0 0o0 halt").is_err());
 }
 
 fn inst<'a>() -> impl Parser<'a, Inst, SyntaxError> {
@@ -208,12 +757,79 @@ fn parse_inst() {
     );
 }
 
-fn addr<'a>() -> impl Parser<'a, Addr, SyntaxError> {
+fn operand<'a>() -> impl Parser<'a, Operand, SyntaxError> {
     move |ctx| {
-        find_any((bin(), oct(), hex()))
-            .parse(ctx)
-            .map_result(|addr| Addr::from(addr << ADDR_POS))
-            .map_error(|err| err.with_message(ExpectedAddr))
+        find_any((
+            move |ctx| addr().parse(ctx).map_result(Operand::Addr),
+            move |ctx| {
+                symbol()
+                    .parse(ctx)
+                    .map_result(|name| Operand::Symbol(name.into()))
+            },
+        ))
+        .parse(ctx)
+    }
+}
+
+fn symbol<'a>() -> impl Parser<'a, &'a str, SyntaxError> {
+    move |ctx| {
+        take_all((
+            optional(is('.')),
+            is(symbol_start),
+            take(0.., is(symbol_char)),
+        ))
+        .parse(ctx)
+        .map_error(|err| err.with_message(ExpectedSymbol))
+    }
+}
+
+fn symbol_start(character: char) -> bool {
+    character.is_ascii_alphabetic() || character == '_'
+}
+
+fn symbol_char(character: char) -> bool {
+    character.is_ascii_alphanumeric() || character == '_'
+}
+
+#[test]
+fn parse_symbol() {
+    assert_eq!("carry", symbol().parse("carry = 0o12").unwrap_result());
+    assert_eq!("_out_1", symbol().parse("_out_1").unwrap_result());
+    assert_eq!(".tmp", symbol().parse(".tmp").unwrap_result());
+    assert!(symbol().parse("1out").is_err());
+}
+
+fn builtin<'a>() -> impl Parser<'a, u32, SyntaxError> {
+    move |ctx: ParserContext<'a>| {
+        let (ret_ctx, name) = symbol().parse(ctx.clone())?;
+
+        match Addr::from_symbol(name) {
+            Some(addr) => Ok((ret_ctx, addr.val())),
+            None => {
+                let error = ParserError::Range(ctx.bounds.clone());
+
+                Err((ctx, error))
+            }
+        }
+    }
+}
+
+fn addr<'a>() -> impl Parser<'a, Addr, SyntaxError> {
+    move |ctx: ParserContext<'a>| {
+        let (ret_ctx, val) = find_any((number(), builtin()))
+            .parse(ctx.clone())
+            .map_error(|err| err.with_message(ExpectedAddr))?;
+
+        match Addr::from_val(val) {
+            Some(addr) => Ok((ret_ctx, addr)),
+            None => {
+                let literal = &ctx.input[ctx.bounds.end..ret_ctx.bounds.end];
+                let error = ParserError::Range(ctx.bounds.clone())
+                    .with_message(AddrRange(literal.into()));
+
+                Err((ctx, error))
+            }
+        }
     }
 }
 
@@ -227,20 +843,60 @@ fn parse_addr() {
         Addr::from(0 << ADDR_POS),
         addr().parse("0o0").unwrap_result()
     );
+    assert_eq!(
+        Addr::from(0o71 << ADDR_POS),
+        addr().parse("RR").unwrap_result()
+    );
+    assert_eq!(
+        Addr::from(0o63 << ADDR_POS),
+        addr().parse("in3").unwrap_result()
+    );
     assert!(addr().parse("0o88").is_err());
+    assert!(addr().parse("RRR").is_err());
     assert!(addr().parse("123").is_err());
     assert!(addr().parse("").is_err());
 }
 
 fn ctrl<'a>() -> impl Parser<'a, Ctrl, SyntaxError> {
     move |ctx| {
-        find_any((bin(), oct(), hex()))
+        find_any((ctrl_number(), ctrl_name()))
             .parse(ctx)
-            .map_result(Ctrl::from)
             .map_error(|err| err.with_message(ExpectedCtrl))
     }
 }
 
+fn ctrl_number<'a>() -> impl Parser<'a, Ctrl, SyntaxError> {
+    move |ctx: ParserContext<'a>| {
+        let (ret_ctx, val) = number().parse(ctx.clone())?;
+
+        match Ctrl::from_val(val) {
+            Some(ctrl) => Ok((ret_ctx, ctrl)),
+            None => {
+                let literal = &ctx.input[ctx.bounds.end..ret_ctx.bounds.end];
+                let error = ParserError::Range(ctx.bounds.clone())
+                    .with_message(CtrlRange(literal.into()));
+
+                Err((ctx, error))
+            }
+        }
+    }
+}
+
+fn ctrl_name<'a>() -> impl Parser<'a, Ctrl, SyntaxError> {
+    move |ctx: ParserContext<'a>| {
+        let (ret_ctx, name) = symbol().parse(ctx.clone())?;
+
+        match Ctrl::from_symbol(name) {
+            Some(ctrl) => Ok((ret_ctx, ctrl)),
+            None => {
+                let error = ParserError::Range(ctx.bounds.clone());
+
+                Err((ctx, error))
+            }
+        }
+    }
+}
+
 #[test]
 fn parse_ctrl() {
     assert_eq!(
@@ -267,6 +923,14 @@ fn parse_ctrl() {
         Ctrl::from(CtrlKind::Null),
         ctrl().parse("0b0").unwrap_result()
     );
+    assert_eq!(
+        Ctrl::from(CtrlKind::CopyShift),
+        ctrl().parse("shift").unwrap_result()
+    );
+    assert_eq!(
+        Ctrl::from(CtrlKind::StopTape),
+        ctrl().parse("STOP").unwrap_result()
+    );
     assert!(ctrl().parse("0b22").is_err());
     assert!(ctrl().parse("22").is_err());
     assert!(ctrl().parse("").is_err());
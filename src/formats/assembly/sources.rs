@@ -0,0 +1,192 @@
+use super::error::AssembleError;
+use super::parser::{self, Located, Spanned, Statement, SyntaxError};
+use chonk::framework::Parser;
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+/// a file that was read while assembling
+#[derive(Debug, Clone, PartialEq)]
+pub struct Source {
+    /// the path of the file
+    pub file: PathBuf,
+    /// the contents of the file
+    pub text: String,
+    /// where the file starts in the combined span of all sources
+    pub offset: usize,
+}
+
+/// every file that makes up a program, with the statements parsed from them
+///
+/// spans of statements index into the combined text of all files, where each
+/// file is placed after the previous one, so a span alone is enough to find
+/// the file it came from.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Sources {
+    /// directories searched for included files that are not found relative
+    /// to the file including them
+    pub include_paths: Vec<PathBuf>,
+    /// every file read so far, in the order they were read
+    pub sources: Vec<Source>,
+    /// files currently being read, used to catch include cycles
+    stack: Vec<PathBuf>,
+}
+
+impl Sources {
+    /// create an empty set of sources with directories to search for
+    /// included files
+    pub fn new(include_paths: &[PathBuf]) -> Sources {
+        Sources {
+            include_paths: include_paths.into(),
+            ..Sources::default()
+        }
+    }
+
+    /// parse the text of a file, replacing include directives with the
+    /// statements of the files they name
+    pub fn parse(
+        &mut self,
+        file: &Path,
+        text: &str,
+    ) -> (Vec<Spanned>, Vec<Located>) {
+        let offset = self
+            .sources
+            .last()
+            .map_or(0, |source| source.offset + source.text.len() + 1);
+        let shift = |span: Range<usize>| span.start + offset..span.end + offset;
+        let mut statements = Vec::new();
+        let mut errors = Vec::new();
+
+        self.sources.push(Source {
+            file: file.into(),
+            text: text.into(),
+            offset,
+        });
+        self.stack.push(canonical(file));
+
+        let results = match parser::statements_recovering().parse(text) {
+            Ok((_, results)) => results,
+            Err((_, error)) => vec![Err(parser::locate(text, &error))],
+        };
+
+        for result in results {
+            match result {
+                Ok((span, Statement::Include(path))) => {
+                    let span = shift(span);
+
+                    match self.include(file, &path) {
+                        Ok((mut included, mut failed)) => {
+                            statements.append(&mut included);
+                            errors.append(&mut failed);
+                        }
+                        Err(error) => errors.push((span, error)),
+                    }
+                }
+                Ok((span, statement)) => {
+                    statements.push((shift(span), statement))
+                }
+                Err((span, error)) => errors.push((shift(span), error)),
+            }
+        }
+
+        self.stack.pop();
+
+        (statements, errors)
+    }
+
+    /// read and parse a file named by an include directive
+    fn include(
+        &mut self,
+        from: &Path,
+        path: &str,
+    ) -> Result<(Vec<Spanned>, Vec<Located>), SyntaxError> {
+        let file = self.find(from, path);
+
+        if self.stack.contains(&canonical(&file)) {
+            return Err(SyntaxError::RecursiveInclude(path.into()));
+        }
+
+        let text = fs::read_to_string(&file).map_err(|error| {
+            SyntaxError::IncludeFailed(path.into(), error.to_string())
+        })?;
+
+        Ok(self.parse(&file, &text))
+    }
+
+    /// find an included file, relative to the file including it first and
+    /// then in each include path, falling back to the relative path so a
+    /// missing file is reported where it was expected
+    fn find(&self, from: &Path, path: &str) -> PathBuf {
+        let relative = from.parent().unwrap_or(Path::new("")).join(path);
+
+        if relative.is_file() {
+            return relative;
+        }
+
+        self.include_paths
+            .iter()
+            .map(|dir| dir.join(path))
+            .find(|file| file.is_file())
+            .unwrap_or(relative)
+    }
+
+    /// get the source a span starts in
+    pub fn source(&self, span: &Range<usize>) -> Option<&Source> {
+        self.sources
+            .iter()
+            .rev()
+            .find(|source| source.offset <= span.start)
+    }
+
+    /// get the file and line number a span starts on
+    pub fn location(&self, span: &Range<usize>) -> Option<(PathBuf, usize)> {
+        let source = self.source(span)?;
+        let start = (span.start - source.offset).min(source.text.len());
+        let line = source.text[..start].matches('\n').count() + 1;
+
+        Some((source.file.clone(), line))
+    }
+
+    /// locate an error in the file it came from, narrowing the span with a
+    /// function given the text of the offending statement
+    pub fn error(
+        &self,
+        span: Range<usize>,
+        error: SyntaxError,
+        narrow: impl Fn(&str, &SyntaxError) -> Option<Range<usize>>,
+    ) -> AssembleError {
+        let source = self.source(&span).expect("span outside of sources");
+        let start = span.start - source.offset;
+        let end = (span.end - source.offset).min(source.text.len());
+        let span = narrow(&source.text[start..end], &error)
+            .map_or(start..end, |narrow| {
+                start + narrow.start..start + narrow.end
+            });
+
+        AssembleError::new(&source.file, &source.text, span, error)
+    }
+}
+
+/// resolve a path to compare files that are included in different ways
+fn canonical(file: &Path) -> PathBuf {
+    fs::canonicalize(file).unwrap_or_else(|_| file.into())
+}
+
+#[test]
+fn locate_sources() {
+    let mut sources = Sources::new(&[]);
+    let (statements, errors) =
+        sources.parse(Path::new("main.asm"), "ONE RR\ninclude \"none.asm\"\n");
+
+    assert_eq!(1, statements.len());
+    assert_eq!(1, errors.len());
+
+    let (span, error) = errors[0].clone();
+    let error = sources.error(span, error, |_, _| None);
+
+    assert_eq!(
+        (Path::new("main.asm"), 2, 1),
+        (error.file.as_path(), error.line, error.column)
+    );
+    assert!(matches!(error.error, SyntaxError::IncludeFailed(..)));
+}
@@ -0,0 +1,152 @@
+use super::parser::{Operand, Spanned, Statement, SyntaxError};
+use crate::data::*;
+use std::collections::HashMap;
+use std::ops::Range;
+
+/// named addresses defined in an assembly file
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Symbols(HashMap<String, Addr>);
+
+impl Symbols {
+    /// create an empty symbol table
+    pub fn new() -> Symbols {
+        Symbols::default()
+    }
+
+    /// get the address of a symbol
+    pub fn get(&self, name: &str) -> Option<Addr> {
+        self.0.get(name).copied()
+    }
+
+    /// define a new symbol, symbols can only be defined once and can not
+    /// replace the builtin names for hardware addresses
+    pub fn define(
+        &mut self,
+        name: &str,
+        addr: Addr,
+    ) -> Result<(), SyntaxError> {
+        if self.0.contains_key(name) || Addr::from_symbol(name).is_some() {
+            return Err(SyntaxError::Redefinition(name.into()));
+        }
+
+        self.0.insert(name.into(), addr);
+
+        Ok(())
+    }
+
+    /// get the address an operand refers to
+    pub fn lookup(&self, operand: &Operand) -> Result<Addr, SyntaxError> {
+        match operand {
+            Operand::Addr(addr) => Ok(*addr),
+            Operand::Symbol(name) => self
+                .get(name)
+                .ok_or_else(|| SyntaxError::UndefinedSymbol(name.clone())),
+        }
+    }
+}
+
+/// resolve symbols in statements to produce nodes
+///
+/// symbol definitions are collected first, so words can refer to symbols that
+/// are defined further down the file, while a definition can only refer to
+/// symbols that are defined above it.
+pub fn resolve(
+    statements: &[Spanned],
+) -> Result<Nodes, Vec<(Range<usize>, SyntaxError)>> {
+    let mut symbols = Symbols::new();
+    let mut errors = Vec::new();
+    let mut nodes = Vec::new();
+
+    for (span, statement) in statements {
+        if let Statement::Define(name, operand) = statement {
+            if let Err(error) = symbols
+                .lookup(operand)
+                .and_then(|addr| symbols.define(name, addr))
+            {
+                errors.push((span.clone(), error));
+            }
+        }
+    }
+
+    for (span, statement) in statements {
+        match statement {
+            Statement::Comment(text) => nodes.push(Node::Comment(text.clone())),
+            Statement::Define(..) => {}
+            Statement::Macro(..) | Statement::Endm | Statement::Call(..) => {}
+            // includes are replaced by the statements of the file they name
+            // when sources are parsed, any left over were never read
+            Statement::Include(path) => errors.push((
+                span.clone(),
+                SyntaxError::UnresolvedInclude(path.clone()),
+            )),
+            Statement::Word(inst, operand, ctrl) => {
+                match symbols.lookup(operand) {
+                    Ok(addr) => nodes.push(Node::Word(*inst, addr, *ctrl)),
+                    Err(error) => errors.push((span.clone(), error)),
+                }
+            }
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(Nodes(nodes))
+    } else {
+        errors.sort_by_key(|(span, _)| span.start);
+
+        Err(errors)
+    }
+}
+
+#[test]
+fn resolve_symbols() {
+    use super::parser::statements;
+    use chonk::prelude::*;
+
+    let asm = r"
+    STO OUT0 0b0
+    OUT0 = PR0
+    LD carry 0b0
+    carry = 0o12
+    ";
+
+    let expected = Nodes(vec![
+        Node::Word(
+            Inst::from(InstKind::Sto),
+            Addr::from(0o50 << ADDR_POS),
+            Ctrl::from(CtrlKind::Null),
+        ),
+        Node::Word(
+            Inst::from(InstKind::Ld),
+            Addr::from(0o12 << ADDR_POS),
+            Ctrl::from(CtrlKind::Null),
+        ),
+    ]);
+
+    let statements = statements().parse(asm).unwrap_result();
+
+    assert_eq!(Ok(expected), resolve(&statements));
+}
+
+#[test]
+fn resolve_errors() {
+    use super::parser::statements;
+    use chonk::prelude::*;
+
+    let asm = "a = 0o1\nb = c\na = 0o2\nLD d 0b0\nrr = R1\n";
+    let statements = statements().parse(asm).unwrap_result();
+    let errors: Vec<_> = resolve(&statements)
+        .unwrap_err()
+        .into_iter()
+        .map(|(span, error)| (asm[..span.start].lines().count() + 1, error))
+        .collect();
+
+    assert_eq!(
+        vec![
+            (2, SyntaxError::UndefinedSymbol("c".into())),
+            (3, SyntaxError::Redefinition("a".into())),
+            (4, SyntaxError::UndefinedSymbol("d".into())),
+            (5, SyntaxError::Redefinition("rr".into())),
+        ],
+        errors
+    );
+}
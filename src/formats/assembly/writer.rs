@@ -0,0 +1,211 @@
+use crate::data::{Addr, Ctrl, CtrlKind, Inst, Node, Nodes};
+use std::io::{prelude::*, BufWriter, Result};
+
+/// number format used for addresses and io control when writing assembly
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Radix {
+    Bin,
+    Oct,
+    Dec,
+    Hex,
+}
+
+/// letter case used for instruction mnemonics when writing assembly
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Case {
+    Upper,
+    Lower,
+}
+
+/// options controlling how nodes are written as assembly
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Style {
+    /// number format for addresses and io control
+    pub radix: Radix,
+    /// letter case for instruction mnemonics
+    pub case: Case,
+    /// pad mnemonics so that addresses line up in a column
+    pub align: bool,
+    /// use builtin names like RR or PR0 for addresses that have one, and
+    /// short names for io control bits, leaving out null io control bits
+    pub symbols: bool,
+}
+
+impl Default for Style {
+    fn default() -> Style {
+        Style {
+            radix: Radix::Bin,
+            case: Case::Upper,
+            align: true,
+            symbols: false,
+        }
+    }
+}
+
+impl Style {
+    /// create a new style with the provided radix
+    pub fn with_radix(self, radix: Radix) -> Style {
+        Style { radix, ..self }
+    }
+
+    /// create a new style with the provided mnemonic case
+    pub fn with_case(self, case: Case) -> Style {
+        Style { case, ..self }
+    }
+
+    /// create a new style with column alignment turned on or off
+    pub fn with_align(self, align: bool) -> Style {
+        Style { align, ..self }
+    }
+
+    /// create a new style with builtin address names turned on or off
+    pub fn with_symbols(self, symbols: bool) -> Style {
+        Style { symbols, ..self }
+    }
+
+    fn mnemonic(&self, inst: Inst) -> String {
+        let name = match self.case {
+            Case::Upper => inst.name().to_uppercase(),
+            Case::Lower => inst.name().to_lowercase(),
+        };
+
+        if self.align {
+            format!("{:<4}", name)
+        } else {
+            name
+        }
+    }
+
+    fn addr(&self, addr: Addr) -> String {
+        let literal = match self.radix {
+            Radix::Bin => format!("0b{:b}", addr),
+            Radix::Oct => format!("0o{:o}", addr),
+            Radix::Dec => format!("0d{:02}", addr.val()),
+            Radix::Hex => format!("0x{:x}", addr),
+        };
+
+        match addr.symbol() {
+            Some(name) if self.symbols && self.align => {
+                format!("{:<width$}", name, width = literal.len())
+            }
+            Some(name) if self.symbols => name,
+            _ => literal,
+        }
+    }
+
+    fn ctrl(&self, ctrl: Ctrl) -> Option<String> {
+        match ctrl.symbol() {
+            _ if self.symbols && ctrl.kind() == CtrlKind::Null => None,
+            Some(name) if self.symbols => Some(name.into()),
+            _ => Some(match self.radix {
+                Radix::Bin => format!("0b{:b}", ctrl),
+                Radix::Oct => format!("0o{:o}", ctrl),
+                Radix::Dec => format!("0d{}", ctrl.val()),
+                Radix::Hex => format!("0x{:x}", ctrl),
+            }),
+        }
+    }
+}
+
+/// format a single node as a line of assembly, without a line ending
+pub fn format_node(node: &Node, style: &Style) -> String {
+    format_named(node, None, style)
+}
+
+/// format a single node as a line of assembly, using a symbol in place of
+/// the address of a word
+pub fn format_named(
+    node: &Node,
+    symbol: Option<String>,
+    style: &Style,
+) -> String {
+    match node {
+        Node::Comment(text) => format!(";{}", text),
+        Node::Word(inst, addr, ctrl) => {
+            let name = style.mnemonic(*inst);
+            let addr = match symbol {
+                Some(symbol) if style.align => {
+                    let literal = style.with_symbols(false).addr(*addr);
+
+                    format!("{:<width$}", symbol, width = literal.len())
+                }
+                Some(symbol) => symbol,
+                None => style.addr(*addr),
+            };
+
+            match style.ctrl(*ctrl) {
+                Some(ctrl) => format!("{} {} {}", name, addr, ctrl),
+                None => format!("{} {}", name, addr.trim_end()),
+            }
+        }
+    }
+}
+
+/// format an address as it would be written in a word, without padding
+pub fn format_addr(addr: Addr, style: &Style) -> String {
+    style.with_align(false).addr(addr)
+}
+
+#[test]
+fn format_word() {
+    use crate::data::*;
+
+    let node = Node::Word(
+        Inst::from(InstKind::Or),
+        Addr::from(9 << ADDR_POS),
+        Ctrl::from(CtrlKind::Null),
+    );
+
+    assert_eq!("OR   0b001001 0b00", format_node(&node, &Style::default()));
+    assert_eq!(
+        "or 0o11 0o0",
+        format_node(
+            &node,
+            &Style::default()
+                .with_radix(Radix::Oct)
+                .with_case(Case::Lower)
+                .with_align(false)
+        )
+    );
+    assert_eq!(
+        "OR   0x09 0x0",
+        format_node(&node, &Style::default().with_radix(Radix::Hex))
+    );
+    assert_eq!(
+        "OR   0d09 0d0",
+        format_node(&node, &Style::default().with_radix(Radix::Dec))
+    );
+    assert_eq!(
+        "OR   R9",
+        format_node(&node, &Style::default().with_symbols(true))
+    );
+    assert_eq!(
+        "NOP0 R9       stop",
+        format_node(
+            &Node::Word(
+                Inst::from(InstKind::Nop0),
+                Addr::from(9 << ADDR_POS),
+                Ctrl::from(CtrlKind::StopTape),
+            ),
+            &Style::default().with_symbols(true)
+        )
+    );
+}
+
+/// serialize nodes to assembly with any writer in a particular style
+pub fn serialize_with(
+    output: &mut impl Write,
+    nodes: &Nodes,
+    style: &Style,
+) -> Result<()> {
+    let mut buf = BufWriter::new(output);
+    let Nodes(nodes) = nodes;
+
+    for node in nodes {
+        writeln!(buf, "{}", format_node(node, style))?;
+    }
+
+    buf.flush()?;
+
+    Ok(())
+}
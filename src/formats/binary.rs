@@ -1,18 +1,84 @@
 use crate::data::{Nodes, *};
 use bitbit::{BitReader, BitWriter};
 use std::fs::File;
-use std::io::{prelude::*, BufReader, BufWriter, Result};
-use std::path::PathBuf;
+use std::io::{prelude::*, BufReader, BufWriter, Error, ErrorKind, Result};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// most chips a word can be split across
+pub const MAX_CHIPS: usize = 3;
+
+/// width of each chip in a split rom
+pub const CHIP_BITS: usize = 8;
+
+/// most words read from a rom image, addresses past this are rejected rather
+/// than filling the gap up to them
+pub const MAX_WORDS: usize = 1 << 16;
+
+/// most bytes read from a rom image, enough for the most words with each in
+/// its own 16-bit location
+pub const MAX_BYTES: usize = MAX_WORDS * 2;
+
+/// bytes at the start of every binary container
+pub const MAGIC: &[u8; 8] = b"UE14500\0";
+
+/// version of the binary container written
+pub const VERSION: u8 = 1;
+
+/// how words are laid out in the bytes of a rom image
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Layout {
+    /// each word in its own 16-bit location, most significant byte first
+    #[default]
+    Word,
+    /// words packed together as 12-bit values, the same as a binary file
+    Packed,
+}
+
+/// details about a program stored in a binary container
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Metadata {
+    /// name of the program
+    pub name: Option<String>,
+    /// notes on how to run the program
+    pub notes: Option<String>,
+}
 
 /// read a file from disk and deserialise words from binary
 pub fn read_file(path: PathBuf) -> Result<Nodes> {
-    let mut buffer = File::open(path).expect("error opening file for reading");
+    let mut buffer = File::open(path)?;
 
     deserialize(&mut buffer)
 }
 
 /// deserialize words from binary with any reader
+///
+/// reads a container when the input starts with its magic bytes, otherwise
+/// the input is read as a raw bitstream.
 pub fn deserialize(input: &mut impl Read) -> Result<Nodes> {
+    deserialize_with_metadata(input).map(|(nodes, _)| nodes)
+}
+
+/// deserialize words from binary with any reader, along with the metadata
+/// when the input is a container
+pub fn deserialize_with_metadata(
+    input: &mut impl Read,
+) -> Result<(Nodes, Option<Metadata>)> {
+    let mut bytes = Vec::new();
+
+    input.read_to_end(&mut bytes)?;
+
+    if bytes.starts_with(MAGIC) {
+        let (nodes, metadata) = read_container(&bytes)?;
+
+        Ok((nodes, Some(metadata)))
+    } else {
+        Ok((deserialize_raw(&mut bytes.as_slice())?, None))
+    }
+}
+
+/// deserialize words from a raw bitstream with any reader
+pub fn deserialize_raw(input: &mut impl Read) -> Result<Nodes> {
     let buf = BufReader::new(input);
     let mut bitreader: BitReader<_, bitbit::MSB> = BitReader::new(buf);
     let mut nodes = Vec::new();
@@ -29,8 +95,7 @@ pub fn deserialize(input: &mut impl Read) -> Result<Nodes> {
 
 /// serialize words to binary and write a file to disk
 pub fn write_file(path: PathBuf, words: Nodes) -> Result<()> {
-    let mut buffer =
-        File::create(path).expect("error opening file for writing");
+    let mut buffer = File::create(path)?;
 
     serialize(&mut buffer, words)
 }
@@ -54,3 +119,490 @@ pub fn serialize(output: &mut impl Write, nodes: Nodes) -> Result<()> {
 
     Ok(())
 }
+
+/// serialize words to a binary container and write a file to disk
+pub fn write_container_file(
+    path: PathBuf,
+    nodes: Nodes,
+    metadata: &Metadata,
+) -> Result<()> {
+    let mut buffer = File::create(path)?;
+
+    serialize_container(&mut buffer, nodes, metadata)
+}
+
+/// serialize nodes to a binary container with any writer
+///
+/// the container holds the magic bytes, a version, the number of words, the
+/// name and notes as length prefixed text, the words as a bitstream and a
+/// crc-32 of everything before it. numbers are stored most significant byte
+/// first.
+pub fn serialize_container(
+    output: &mut impl Write,
+    nodes: Nodes,
+    metadata: &Metadata,
+) -> Result<()> {
+    let words = nodes.0.iter().filter(|node| Word::try_from(*node).is_ok());
+    let count = u32::try_from(words.count()).map_err(|_| {
+        Error::new(ErrorKind::InvalidInput, "too many words for a container")
+    })?;
+    let mut bytes = MAGIC.to_vec();
+
+    bytes.push(VERSION);
+    bytes.extend_from_slice(&count.to_be_bytes());
+
+    for text in [&metadata.name, &metadata.notes] {
+        let text = text.as_deref().unwrap_or("");
+        let len = u16::try_from(text.len()).map_err(|_| {
+            Error::new(ErrorKind::InvalidInput, "metadata is too long")
+        })?;
+
+        bytes.extend_from_slice(&len.to_be_bytes());
+        bytes.extend_from_slice(text.as_bytes());
+    }
+
+    serialize(&mut bytes, nodes)?;
+
+    let crc = crc32(&bytes);
+
+    bytes.extend_from_slice(&crc.to_be_bytes());
+    output.write_all(&bytes)?;
+    output.flush()
+}
+
+fn read_container(bytes: &[u8]) -> Result<(Nodes, Metadata)> {
+    let invalid = |message: &str| Error::new(ErrorKind::InvalidData, message);
+    let truncated = || invalid("container is truncated");
+    let (body, crc) = bytes
+        .len()
+        .checked_sub(4)
+        .map(|end| bytes.split_at(end))
+        .ok_or_else(truncated)?;
+
+    if crc32(body) != u32::from_be_bytes(crc.try_into().unwrap()) {
+        return Err(invalid("container checksum does not match"));
+    }
+
+    let mut rest = &body[MAGIC.len()..];
+    let mut take = |len: usize| {
+        let (taken, remaining) =
+            rest.split_at_checked(len).ok_or_else(truncated)?;
+
+        rest = remaining;
+
+        Ok::<_, Error>(taken)
+    };
+    let version = take(1)?[0];
+
+    if version != VERSION {
+        return Err(invalid(&format!(
+            "unsupported container version {}",
+            version
+        )));
+    }
+
+    let count = u32::from_be_bytes(take(4)?.try_into().unwrap()) as usize;
+    let mut texts = Vec::new();
+
+    for _ in 0..2 {
+        let len = u16::from_be_bytes(take(2)?.try_into().unwrap());
+        let text = String::from_utf8(take(len.into())?.to_vec())
+            .map_err(|_| invalid("metadata is not valid text"))?;
+
+        texts.push((!text.is_empty()).then_some(text));
+    }
+
+    let payload = take((count * 12).div_ceil(8))?;
+
+    if !rest.is_empty() {
+        return Err(invalid("container has trailing bytes"));
+    }
+
+    let Nodes(mut nodes) = deserialize_raw(&mut &payload[..])?;
+    let notes = texts.pop().flatten();
+    let name = texts.pop().flatten();
+
+    nodes.truncate(count);
+
+    Ok((Nodes(nodes), Metadata { name, notes }))
+}
+
+/// crc-32 as used by zip and png
+pub fn crc32(bytes: &[u8]) -> u32 {
+    !bytes.iter().fold(!0, |crc, byte| {
+        (0..8).fold(crc ^ u32::from(*byte), |crc, _| {
+            (crc >> 1) ^ (0xEDB8_8320 & (crc & 1).wrapping_neg())
+        })
+    })
+}
+
+/// a program with a mix of bits in each word, for round trip tests
+#[cfg(test)]
+pub(crate) fn sample_nodes(words: u32) -> Nodes {
+    Nodes(
+        (0..words)
+            .map(|val| Word::from(val * 97 % 4096).into())
+            .collect(),
+    )
+}
+
+#[test]
+fn container_round_trip() {
+    let nodes = sample_nodes(5);
+    let metadata = Metadata {
+        name: Some("blink".into()),
+        notes: Some("set IN0 before starting".into()),
+    };
+    let mut bytes = Vec::new();
+
+    assert_eq!(0xCBF4_3926, crc32(b"123456789"));
+
+    serialize_container(&mut bytes, nodes.clone(), &metadata).unwrap();
+
+    assert_eq!(
+        (nodes.clone(), Some(metadata)),
+        deserialize_with_metadata(&mut bytes.as_slice()).unwrap()
+    );
+
+    let mut raw = Vec::new();
+
+    serialize(&mut raw, nodes.clone()).unwrap();
+
+    assert_eq!(nodes, deserialize(&mut raw.as_slice()).unwrap());
+
+    let mut corrupt = bytes.clone();
+
+    corrupt[20] ^= 1;
+
+    assert!(deserialize(&mut corrupt.as_slice()).is_err());
+    assert!(deserialize(&mut &bytes[..bytes.len() - 1]).is_err());
+}
+
+/// lay out words as bytes for a rom image
+pub fn to_bytes(nodes: Nodes, layout: Layout) -> Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+
+    match layout {
+        Layout::Word => {
+            let Nodes(nodes) = nodes;
+
+            for node in nodes {
+                if let Ok(word) = Word::try_from(node) {
+                    let bin = u32::from(word);
+
+                    bytes.extend_from_slice(&[(bin >> 8) as u8, bin as u8]);
+                }
+            }
+        }
+        Layout::Packed => serialize(&mut bytes, nodes)?,
+    }
+
+    Ok(bytes)
+}
+
+/// read words back from the bytes of a rom image
+pub fn from_bytes(bytes: &[u8], layout: Layout) -> Result<Nodes> {
+    match layout {
+        Layout::Word => {
+            let pairs = bytes.chunks_exact(2);
+
+            if !pairs.remainder().is_empty() {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    "image ends halfway through a 16-bit word",
+                ));
+            }
+
+            Ok(Nodes(
+                pairs
+                    .map(|pair| {
+                        let bin = u32::from(pair[0]) << 8 | u32::from(pair[1]);
+
+                        Word::from(bin).into()
+                    })
+                    .collect(),
+            ))
+        }
+        Layout::Packed => deserialize_raw(&mut &bytes[..]),
+    }
+}
+
+#[test]
+fn word_layout_bytes() {
+    let bytes = [0x0A, 0xFD, 0x01, 0x0A];
+    let nodes = from_bytes(&bytes, Layout::Word).unwrap();
+
+    assert_eq!(bytes.to_vec(), to_bytes(nodes, Layout::Word).unwrap());
+    assert!(from_bytes(&bytes[..3], Layout::Word).is_err());
+}
+
+/// which bits of a word are stored in each chip of a split rom
+///
+/// each chip lists the word bits it holds, most significant first. chips with
+/// fewer than eight bits keep them in the low bits of each byte.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Split(pub Vec<Vec<u32>>);
+
+impl Default for Split {
+    /// instruction and high address bits in one chip, the low address bits
+    /// and io control in the other
+    fn default() -> Split {
+        Split(vec![(4..12).rev().collect(), (0..4).rev().collect()])
+    }
+}
+
+impl Split {
+    /// check that every bit of a word is stored in exactly one chip
+    pub fn validate(&self) -> std::result::Result<(), String> {
+        let Split(chips) = self;
+        let mut bits: Vec<u32> = chips.iter().flatten().copied().collect();
+
+        if !(2..=MAX_CHIPS).contains(&chips.len()) {
+            return Err(format!("expected 2 to {} chips", MAX_CHIPS));
+        }
+
+        if let Some(chip) = chips.iter().position(|chip| chip.len() > CHIP_BITS)
+        {
+            return Err(format!(
+                "chip {} has more than {} bits",
+                chip, CHIP_BITS
+            ));
+        }
+
+        bits.sort_unstable();
+
+        if bits != (0..12).collect::<Vec<_>>() {
+            return Err("expected each of the 12 word bits once".into());
+        }
+
+        Ok(())
+    }
+
+    /// the byte a chip holds for a word
+    fn byte(&self, chip: usize, bin: u32) -> u8 {
+        self.0[chip]
+            .iter()
+            .fold(0, |byte, bit| byte << 1 | (bin >> bit & 1) as u8)
+    }
+
+    /// put the bits a chip holds back in their place in a word
+    fn bits(&self, chip: usize, byte: u8) -> u32 {
+        let bits = &self.0[chip];
+
+        bits.iter().enumerate().fold(0, |bin, (pos, bit)| {
+            let shift = bits.len() - 1 - pos;
+
+            bin | u32::from(byte >> shift & 1) << bit
+        })
+    }
+}
+
+impl FromStr for Split {
+    type Err = String;
+
+    /// parse chips separated by `,`, each a list of bits or `from-to` bit
+    /// ranges separated by `+`, so the default split is `11-4,3-0`
+    fn from_str(val: &str) -> std::result::Result<Split, Self::Err> {
+        let bit = |val: &str| {
+            val.trim()
+                .parse::<u32>()
+                .ok()
+                .filter(|bit| *bit < 12)
+                .ok_or_else(|| {
+                    format!("expected a bit from 0 to 11: `{}`", val)
+                })
+        };
+        let mut chips = Vec::new();
+
+        for chip in val.split(',') {
+            let mut bits = Vec::new();
+
+            for range in chip.split('+') {
+                match range.split_once('-') {
+                    Some((from, to)) => {
+                        let (from, to) = (bit(from)?, bit(to)?);
+
+                        if from >= to {
+                            bits.extend((to..=from).rev());
+                        } else {
+                            bits.extend(from..=to);
+                        }
+                    }
+                    None => bits.push(bit(range)?),
+                }
+            }
+
+            chips.push(bits);
+        }
+
+        let split = Split(chips);
+
+        split.validate()?;
+
+        Ok(split)
+    }
+}
+
+/// paths of the chip images for a split rom, with the chip number added
+/// before the extension, like `prog.0.bin` and `prog.1.bin`
+pub fn split_paths(path: &Path, chips: usize) -> Vec<PathBuf> {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let extension = path.extension().map_or(String::new(), |extension| {
+        format!(".{}", extension.to_string_lossy())
+    });
+
+    (0..chips)
+        .map(|chip| {
+            path.with_file_name(format!("{}.{}{}", stem, chip, extension))
+        })
+        .collect()
+}
+
+/// read the chip images of a split rom from disk and merge them into words
+pub fn read_split(path: &Path, split: &Split) -> Result<Nodes> {
+    let mut inputs = split_paths(path, split.0.len())
+        .into_iter()
+        .map(File::open)
+        .collect::<Result<Vec<_>>>()?;
+
+    deserialize_split(&mut inputs, split)
+}
+
+/// merge words from the chip images of a split rom with any readers
+pub fn deserialize_split(
+    inputs: &mut [impl Read],
+    split: &Split,
+) -> Result<Nodes> {
+    split
+        .validate()
+        .map_err(|error| Error::new(ErrorKind::InvalidInput, error))?;
+
+    if inputs.len() != split.0.len() {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("expected {} chip images", split.0.len()),
+        ));
+    }
+
+    let mut images = Vec::new();
+
+    for input in inputs.iter_mut() {
+        let mut bytes = Vec::new();
+
+        input.read_to_end(&mut bytes)?;
+        images.push(bytes);
+    }
+
+    if images.iter().any(|bytes| bytes.len() != images[0].len()) {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            "chip images have different lengths",
+        ));
+    }
+
+    Ok(Nodes(
+        (0..images[0].len())
+            .map(|index| {
+                let bin =
+                    images.iter().enumerate().fold(0, |bin, (chip, bytes)| {
+                        bin | split.bits(chip, bytes[index])
+                    });
+
+                Word::from(bin).into()
+            })
+            .collect(),
+    ))
+}
+
+/// split words across chip images and write them to disk, returning the
+/// paths written
+pub fn write_split(
+    path: &Path,
+    nodes: Nodes,
+    split: &Split,
+) -> Result<Vec<PathBuf>> {
+    let paths = split_paths(path, split.0.len());
+    let mut outputs =
+        paths.iter().map(File::create).collect::<Result<Vec<_>>>()?;
+
+    serialize_split(&mut outputs, nodes, split)?;
+
+    Ok(paths)
+}
+
+/// split words across chip images with any writers, one byte per word in
+/// each image
+pub fn serialize_split(
+    outputs: &mut [impl Write],
+    nodes: Nodes,
+    split: &Split,
+) -> Result<()> {
+    split
+        .validate()
+        .map_err(|error| Error::new(ErrorKind::InvalidInput, error))?;
+
+    if outputs.len() != split.0.len() {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("expected {} chip images", split.0.len()),
+        ));
+    }
+
+    let Nodes(nodes) = nodes;
+    let words: Vec<u32> = nodes
+        .into_iter()
+        .filter_map(|node| Word::try_from(node).ok())
+        .map(u32::from)
+        .collect();
+
+    for (chip, output) in outputs.iter_mut().enumerate() {
+        let bytes: Vec<u8> =
+            words.iter().map(|bin| split.byte(chip, *bin)).collect();
+
+        output.write_all(&bytes)?;
+        output.flush()?;
+    }
+
+    Ok(())
+}
+
+#[test]
+fn split_round_trip() {
+    let nodes = sample_nodes(40);
+
+    for split in ["11-4,3-0", "11-8,7-2,1-0", "0-7,8+9+11+10"] {
+        let split = Split::from_str(split).unwrap();
+        let mut outputs = vec![Vec::new(); split.0.len()];
+
+        serialize_split(&mut outputs, nodes.clone(), &split).unwrap();
+
+        let mut inputs: Vec<&[u8]> =
+            outputs.iter().map(|bytes| bytes.as_slice()).collect();
+
+        assert_eq!(nodes, deserialize_split(&mut inputs, &split).unwrap());
+    }
+}
+
+#[test]
+fn split_bytes() {
+    let split = Split::default();
+    let mut outputs = vec![Vec::new(); 2];
+    let nodes = Nodes(vec![Word::from(0b1010_111111_01).into()]);
+
+    assert_eq!(Ok(split.clone()), Split::from_str("11-4,3-0"));
+
+    serialize_split(&mut outputs, nodes, &split).unwrap();
+
+    assert_eq!(vec![vec![0b1010_1111], vec![0b0000_1101]], outputs);
+    assert!(Split::from_str("11-4").is_err());
+    assert!(Split::from_str("11-3,3-0").is_err());
+    assert!(Split::from_str("11-2,1-0").is_err());
+    assert!(Split::from_str("11-4,3-0,12").is_err());
+    assert_eq!(
+        vec![
+            PathBuf::from("out/prog.0.bin"),
+            PathBuf::from("out/prog.1.bin")
+        ],
+        split_paths(Path::new("out/prog.bin"), 2)
+    );
+}
@@ -0,0 +1,433 @@
+use super::assembly::{writer, Style};
+use super::binary;
+use crate::data::{Addr, Node, Nodes};
+use std::fs::File;
+use std::io::{prelude::*, BufReader, BufWriter, Error, ErrorKind, Result};
+use std::path::{Path, PathBuf};
+
+/// first line of a debug file
+const HEADER: &str = "ue14500 debug 2";
+
+/// where a word came from in the assembly source
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct WordInfo {
+    /// the file and line number the word was assembled from
+    pub source: Option<(PathBuf, usize)>,
+    /// the symbol used for the address of the word
+    pub symbol: Option<String>,
+}
+
+/// the number of words in a program and a crc-32 of them as a binary, used
+/// to check that debug info belongs to the program it is read with
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Fingerprint {
+    pub words: usize,
+    pub crc: u32,
+}
+
+impl Fingerprint {
+    /// the fingerprint of the words in some nodes, ignoring comments
+    pub fn of(nodes: &Nodes) -> Fingerprint {
+        let mut bytes = Vec::new();
+
+        binary::serialize(&mut bytes, nodes.clone())
+            .expect("writing to memory");
+
+        Fingerprint {
+            words: nodes
+                .0
+                .iter()
+                .filter(|node| matches!(node, Node::Word(..)))
+                .count(),
+            crc: binary::crc32(&bytes),
+        }
+    }
+}
+
+/// details about assembled words that binary formats can not hold
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DebugInfo {
+    /// the program the debug info was made for
+    pub program: Fingerprint,
+    /// details of each word, by word index
+    pub words: Vec<WordInfo>,
+    /// comments, along with the index of the word they come before
+    pub comments: Vec<(usize, String)>,
+}
+
+impl DebugInfo {
+    /// the path of the debug file kept alongside a binary, with `.dbg`
+    /// added to the whole file name so every output has its own
+    pub fn path(binary: &Path) -> PathBuf {
+        let mut path = binary.as_os_str().to_owned();
+
+        path.push(".dbg");
+        path.into()
+    }
+
+    /// create debug info with the comments and fingerprint of some nodes
+    pub fn with_comments(self, nodes: &Nodes) -> DebugInfo {
+        let mut words = 0;
+        let mut comments = Vec::new();
+
+        for node in &nodes.0 {
+            match node {
+                Node::Comment(text) => comments.push((words, text.clone())),
+                Node::Word(..) => words += 1,
+            }
+        }
+
+        DebugInfo {
+            program: Fingerprint::of(nodes),
+            comments,
+            ..self
+        }
+    }
+
+    /// put comments back between the words of some nodes, replacing any
+    /// comments already there
+    pub fn annotate(&self, nodes: Nodes) -> Nodes {
+        let Nodes(nodes) = nodes;
+        let mut comments = self.comments.iter().peekable();
+        let mut annotated = Vec::new();
+        let mut words = 0;
+
+        for node in nodes {
+            if let Node::Word(..) = node {
+                while let Some((_, text)) =
+                    comments.next_if(|(index, _)| *index <= words)
+                {
+                    annotated.push(Node::Comment(text.clone()));
+                }
+
+                annotated.push(node);
+                words += 1;
+            }
+        }
+
+        annotated.extend(comments.map(|(_, text)| Node::Comment(text.clone())));
+
+        Nodes(annotated)
+    }
+
+    /// symbols used by words, along with the address each one names, in the
+    /// order they are first used
+    pub fn symbols(&self, nodes: &Nodes) -> Vec<(String, Addr)> {
+        let mut symbols: Vec<(String, Addr)> = Vec::new();
+        let words = nodes.0.iter().filter_map(|node| match node {
+            Node::Word(_, addr, _) => Some(*addr),
+            Node::Comment(_) => None,
+        });
+
+        for (info, addr) in self.words.iter().zip(words) {
+            if let Some(symbol) = &info.symbol {
+                if !symbols.iter().any(|(name, _)| name == symbol) {
+                    symbols.push((symbol.clone(), addr));
+                }
+            }
+        }
+
+        symbols
+    }
+}
+
+/// read a debug file from disk
+pub fn read_file(path: PathBuf) -> Result<DebugInfo> {
+    let mut buffer = File::open(path)?;
+
+    deserialize(&mut buffer)
+}
+
+/// read the debug file kept alongside a binary, if there is one, checking
+/// that it was made for the nodes read from the binary
+pub fn read_sidecar(binary: &Path, nodes: &Nodes) -> Result<Option<DebugInfo>> {
+    let path = DebugInfo::path(binary);
+
+    if !path.is_file() {
+        return Ok(None);
+    }
+
+    let debug = read_file(path)?;
+
+    if debug.program != Fingerprint::of(nodes) {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            "debug file was made for a different program",
+        ));
+    }
+
+    Ok(Some(debug))
+}
+
+/// deserialize debug info with any reader
+pub fn deserialize(input: &mut impl Read) -> Result<DebugInfo> {
+    let mut lines = BufReader::new(input).lines().enumerate();
+    let mut debug = DebugInfo::default();
+
+    let header = match lines.next() {
+        Some((_, line)) => line?,
+        None => String::new(),
+    };
+
+    if header.trim_end() != HEADER {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            "line 1: expected a debug file header",
+        ));
+    }
+
+    let program = match lines.next() {
+        Some((_, line)) => line?,
+        None => String::new(),
+    };
+
+    debug.program = match program.split('\t').collect::<Vec<_>>()[..] {
+        ["program", words, crc] => {
+            words.parse().ok().zip(u32::from_str_radix(crc, 16).ok())
+        }
+        _ => None,
+    }
+    .map(|(words, crc)| Fingerprint { words, crc })
+    .ok_or_else(|| {
+        Error::new(
+            ErrorKind::InvalidData,
+            "line 2: expected the word count and crc of the program",
+        )
+    })?;
+
+    for (index, line) in lines {
+        let line = line?;
+        let invalid = |message: &str| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("line {}: {}", index + 1, message),
+            )
+        };
+        let fields: Vec<&str> = line.split('\t').collect();
+        let number = |field: &str| {
+            field
+                .parse::<usize>()
+                .map_err(|_| invalid("expected a number"))
+        };
+        let past_end = || invalid("word index is past the end of the program");
+
+        match fields[..] {
+            ["word", word, file, line, symbol] => {
+                let word = number(word)?;
+
+                if word >= debug.program.words {
+                    return Err(past_end());
+                }
+
+                let source = match (file, line) {
+                    ("", _) => None,
+                    (file, line) => match number(line)? {
+                        0 => {
+                            return Err(invalid("line numbers start from one"))
+                        }
+                        line => Some((PathBuf::from(file), line)),
+                    },
+                };
+                let symbol = (!symbol.is_empty()).then(|| symbol.to_string());
+
+                if debug.words.len() <= word {
+                    debug.words.resize(word + 1, WordInfo::default());
+                }
+
+                debug.words[word] = WordInfo { source, symbol };
+            }
+            ["comment", word, ref text @ ..] => {
+                let word = number(word)?;
+
+                if word > debug.program.words {
+                    return Err(past_end());
+                }
+
+                debug.comments.push((word, text.join("\t")));
+            }
+            [""] => {}
+            _ => return Err(invalid("expected a word or comment")),
+        }
+    }
+
+    debug.comments.sort_by_key(|(word, _)| *word);
+
+    Ok(debug)
+}
+
+/// serialize debug info and write a file to disk
+pub fn write_file(path: PathBuf, debug: &DebugInfo) -> Result<()> {
+    let mut buffer = File::create(path)?;
+
+    serialize(&mut buffer, debug)
+}
+
+/// serialize debug info with any writer, one word or comment per line with
+/// tabs between fields
+pub fn serialize(output: &mut impl Write, debug: &DebugInfo) -> Result<()> {
+    let mut buf = BufWriter::new(output);
+
+    writeln!(buf, "{}", HEADER)?;
+    writeln!(
+        buf,
+        "program\t{}\t{:08x}",
+        debug.program.words, debug.program.crc
+    )?;
+
+    for (index, info) in debug.words.iter().enumerate() {
+        let (file, line) = match &info.source {
+            Some((file, line)) => {
+                (file.display().to_string(), line.to_string())
+            }
+            None => (String::new(), String::new()),
+        };
+
+        writeln!(
+            buf,
+            "word\t{}\t{}\t{}\t{}",
+            index,
+            file,
+            line,
+            info.symbol.as_deref().unwrap_or("")
+        )?;
+    }
+
+    for (word, text) in &debug.comments {
+        writeln!(buf, "comment\t{}\t{}", word, text)?;
+    }
+
+    buf.flush()
+}
+
+/// write nodes as assembly using the symbols and comments from debug info,
+/// with a definition for each symbol before the first word
+pub fn disassemble(
+    output: &mut impl Write,
+    nodes: &Nodes,
+    debug: &DebugInfo,
+    style: &Style,
+) -> Result<()> {
+    let mut buf = BufWriter::new(output);
+    let symbols = debug.symbols(nodes);
+    let mut words = debug.words.iter();
+
+    for (name, addr) in &symbols {
+        writeln!(buf, "{} = {}", name, writer::format_addr(*addr, style))?;
+    }
+
+    if !symbols.is_empty() {
+        writeln!(buf)?;
+    }
+
+    for node in &debug.annotate(nodes.clone()).0 {
+        let symbol = match node {
+            Node::Word(..) => words.next().and_then(|info| info.symbol.clone()),
+            Node::Comment(_) => None,
+        };
+
+        writeln!(buf, "{}", writer::format_named(node, symbol, style))?;
+    }
+
+    buf.flush()
+}
+
+#[test]
+fn debug_round_trip() {
+    let debug = DebugInfo {
+        program: Fingerprint {
+            words: 2,
+            crc: 0x1234_abcd,
+        },
+        words: vec![
+            WordInfo {
+                source: Some(("main.asm".into(), 3)),
+                symbol: None,
+            },
+            WordInfo {
+                source: Some(("lib/io.asm".into(), 12)),
+                symbol: Some("OUT".into()),
+            },
+        ],
+        comments: vec![(0, " set\tall outputs".into()), (2, "".into())],
+    };
+    let mut bytes = Vec::new();
+
+    serialize(&mut bytes, &debug).unwrap();
+
+    assert_eq!(
+        "ue14500 debug 2\n\
+         program\t2\t1234abcd\n\
+         word\t0\tmain.asm\t3\t\n\
+         word\t1\tlib/io.asm\t12\tOUT\n\
+         comment\t0\t set\tall outputs\n\
+         comment\t2\t\n",
+        String::from_utf8(bytes.clone()).unwrap()
+    );
+    assert_eq!(debug, deserialize(&mut bytes.as_slice()).unwrap());
+    assert!(deserialize(&mut "word\t0\n".as_bytes()).is_err());
+
+    let header = "ue14500 debug 2\nprogram\t2\t1234abcd\n";
+
+    for line in [
+        "word\t2\t\t\t\n",
+        "comment\t3\t\n",
+        "word\t0\tmain.asm\t0\t\n",
+        "word\t99999999999\t\t\t\n",
+    ] {
+        let text = format!("{}{}", header, line);
+
+        assert!(deserialize(&mut text.as_bytes()).is_err());
+    }
+
+    assert_eq!(
+        PathBuf::from("prog.bin.dbg"),
+        DebugInfo::path(Path::new("prog.bin"))
+    );
+}
+
+#[test]
+fn annotate_nodes() {
+    use crate::data::*;
+
+    let nodes = Nodes(vec![
+        Node::Comment(" start".into()),
+        Word::from(0b0100_111001_00).into(),
+        Node::Comment(" write".into()),
+        Word::from(0b1000_101000_00).into(),
+        Node::Comment(" end".into()),
+    ]);
+    let words = Nodes(vec![nodes.0[1].clone(), nodes.0[3].clone()]);
+    let debug = DebugInfo {
+        words: vec![
+            WordInfo::default(),
+            WordInfo {
+                source: None,
+                symbol: Some("OUT".into()),
+            },
+        ],
+        ..DebugInfo::default()
+    }
+    .with_comments(&nodes);
+
+    assert_eq!(nodes, debug.annotate(words.clone()));
+    assert_eq!(Fingerprint::of(&words), debug.program);
+    assert_ne!(
+        Fingerprint::of(&Nodes(vec![nodes.0[1].clone()])),
+        debug.program
+    );
+
+    let mut asm = Vec::new();
+
+    disassemble(&mut asm, &words, &debug, &Style::default()).unwrap();
+
+    assert_eq!(
+        "OUT = 0b101000\n\
+         \n\
+         ; start\n\
+         ONE  0b111001 0b00\n\
+         ; write\n\
+         STO  OUT      0b00\n\
+         ; end\n",
+        String::from_utf8(asm).unwrap()
+    );
+}
@@ -0,0 +1,236 @@
+use super::binary;
+pub use super::binary::Layout;
+use crate::data::Nodes;
+use std::fs::File;
+use std::io::{prelude::*, BufReader, BufWriter, Error, ErrorKind, Result};
+use std::path::PathBuf;
+
+/// number of data bytes in each record
+const RECORD_LEN: usize = 16;
+
+const DATA: u8 = 0x00;
+const END_OF_FILE: u8 = 0x01;
+const EXTENDED_SEGMENT_ADDRESS: u8 = 0x02;
+const START_SEGMENT_ADDRESS: u8 = 0x03;
+const EXTENDED_LINEAR_ADDRESS: u8 = 0x04;
+const START_LINEAR_ADDRESS: u8 = 0x05;
+
+/// read a file from disk and deserialize words from intel hex
+pub fn read_file(path: PathBuf, layout: Layout) -> Result<Nodes> {
+    let mut buffer = File::open(path)?;
+
+    deserialize(&mut buffer, layout)
+}
+
+/// deserialize words from intel hex with any reader
+///
+/// the image starts at address zero, any gaps between records are filled
+/// with zero bytes.
+pub fn deserialize(input: &mut impl Read, layout: Layout) -> Result<Nodes> {
+    let bytes = read_records(BufReader::new(input))?;
+
+    binary::from_bytes(&bytes, layout)
+}
+
+/// serialize words to intel hex and write a file to disk
+pub fn write_file(path: PathBuf, nodes: Nodes, layout: Layout) -> Result<()> {
+    let mut buffer = File::create(path)?;
+
+    serialize(&mut buffer, nodes, layout)
+}
+
+/// serialize nodes to intel hex with any writer
+pub fn serialize(
+    output: &mut impl Write,
+    nodes: Nodes,
+    layout: Layout,
+) -> Result<()> {
+    let bytes = binary::to_bytes(nodes, layout)?;
+
+    write_records(BufWriter::new(output), &bytes)
+}
+
+fn write_records(mut output: impl Write, bytes: &[u8]) -> Result<()> {
+    let mut upper = 0;
+
+    for (index, chunk) in bytes.chunks(RECORD_LEN).enumerate() {
+        let address = index * RECORD_LEN;
+
+        if address >> 16 != upper {
+            upper = address >> 16;
+
+            write_record(
+                &mut output,
+                EXTENDED_LINEAR_ADDRESS,
+                0,
+                &(upper as u16).to_be_bytes(),
+            )?;
+        }
+
+        write_record(&mut output, DATA, address as u16, chunk)?;
+    }
+
+    write_record(&mut output, END_OF_FILE, 0, &[])?;
+    output.flush()
+}
+
+fn write_record(
+    output: &mut impl Write,
+    kind: u8,
+    address: u16,
+    data: &[u8],
+) -> Result<()> {
+    let mut record = vec![data.len() as u8];
+
+    record.extend_from_slice(&address.to_be_bytes());
+    record.push(kind);
+    record.extend_from_slice(data);
+    record.push(checksum(&record));
+
+    write!(output, ":")?;
+
+    for byte in record {
+        write!(output, "{:02X}", byte)?;
+    }
+
+    writeln!(output)
+}
+
+fn read_records(input: impl BufRead) -> Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    let mut base = 0;
+
+    for (index, line) in input.lines().enumerate() {
+        let line = line?;
+        let line = line.trim();
+        let invalid = |message: &str| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("line {}: {}", index + 1, message),
+            )
+        };
+
+        if line.is_empty() {
+            continue;
+        }
+
+        let hex = line
+            .strip_prefix(':')
+            .ok_or_else(|| invalid("expected a record starting with `:`"))?;
+        let record = (0..hex.len())
+            .step_by(2)
+            .map(|pos| {
+                hex.get(pos..pos + 2)
+                    .and_then(|byte| u8::from_str_radix(byte, 16).ok())
+            })
+            .collect::<Option<Vec<u8>>>()
+            .ok_or_else(|| invalid("expected hexadecimal bytes"))?;
+
+        if record.len() < 5 || record.len() != record[0] as usize + 5 {
+            return Err(invalid("record length does not match its data"));
+        }
+
+        if checksum(&record[..record.len() - 1]) != record[record.len() - 1] {
+            return Err(invalid("checksum does not match"));
+        }
+
+        let address = usize::from(record[1]) << 8 | usize::from(record[2]);
+        let data = &record[4..record.len() - 1];
+
+        match record[3] {
+            DATA => {
+                let start = base + address;
+
+                if start + data.len() > binary::MAX_BYTES {
+                    return Err(invalid(&format!(
+                        "address {:#x} is past the largest image of {} bytes",
+                        start,
+                        binary::MAX_BYTES
+                    )));
+                }
+
+                if bytes.len() < start + data.len() {
+                    bytes.resize(start + data.len(), 0);
+                }
+
+                bytes[start..start + data.len()].copy_from_slice(data);
+            }
+            END_OF_FILE => break,
+            EXTENDED_SEGMENT_ADDRESS if data.len() == 2 => {
+                base = (usize::from(data[0]) << 8 | usize::from(data[1])) << 4;
+            }
+            EXTENDED_LINEAR_ADDRESS if data.len() == 2 => {
+                base = (usize::from(data[0]) << 8 | usize::from(data[1])) << 16;
+            }
+            START_SEGMENT_ADDRESS | START_LINEAR_ADDRESS => {}
+            _ => return Err(invalid("unsupported record type")),
+        }
+    }
+
+    Ok(bytes)
+}
+
+/// two's complement of the sum of every byte in a record
+fn checksum(record: &[u8]) -> u8 {
+    record
+        .iter()
+        .fold(0u8, |sum, byte| sum.wrapping_add(*byte))
+        .wrapping_neg()
+}
+
+#[test]
+fn serialize_records() {
+    use crate::data::*;
+
+    let nodes = Nodes(vec![
+        Node::Comment(" skipped".into()),
+        Word::from(0b1010_111111_01).into(),
+        Word::from(0b0001_000010_10).into(),
+    ]);
+    let mut hex = Vec::new();
+
+    serialize(&mut hex, nodes.clone(), Layout::Word).unwrap();
+
+    assert_eq!(
+        ":040000000AFD010AEA\n:00000001FF\n",
+        String::from_utf8(hex).unwrap()
+    );
+
+    let mut hex = Vec::new();
+
+    serialize(&mut hex, nodes, Layout::Packed).unwrap();
+
+    assert_eq!(
+        ":03000000AFD10A73\n:00000001FF\n",
+        String::from_utf8(hex).unwrap()
+    );
+}
+
+#[test]
+fn deserialize_round_trip() {
+    use super::binary::sample_nodes;
+
+    let nodes = sample_nodes(40);
+
+    for layout in [Layout::Word, Layout::Packed] {
+        let mut hex = Vec::new();
+
+        serialize(&mut hex, nodes.clone(), layout).unwrap();
+
+        let read = deserialize(&mut hex.as_slice(), layout).unwrap();
+
+        assert_eq!(nodes, read);
+    }
+
+    let bad = ":0200000001FFFF\n:00000001FF\n";
+
+    assert!(deserialize(&mut bad.as_bytes(), Layout::Word).is_err());
+
+    let far = ":02000004FFFFFC\n:0100000000FF\n:00000001FF\n";
+
+    let error = deserialize(&mut far.as_bytes(), Layout::Word).unwrap_err();
+
+    assert!(error
+        .to_string()
+        .starts_with("line 2: address 0xffff0000 is past"));
+}
@@ -0,0 +1,88 @@
+use crate::data::Nodes;
+use std::fs::File;
+use std::io::{prelude::*, BufReader, BufWriter, Result};
+use std::path::PathBuf;
+
+/// read a file from disk and deserialize nodes from json
+pub fn read_file(path: PathBuf) -> Result<Nodes> {
+    let mut buffer = File::open(path)?;
+
+    deserialize(&mut buffer)
+}
+
+/// deserialize nodes from json with any reader
+///
+/// the raw value of each word is checked against its mnemonic, address and
+/// io control, along with the kind of address and the name of the io control.
+pub fn deserialize(input: &mut impl Read) -> Result<Nodes> {
+    Ok(serde_json::from_reader(BufReader::new(input))?)
+}
+
+/// serialize nodes to json and write a file to disk
+pub fn write_file(path: PathBuf, nodes: &Nodes) -> Result<()> {
+    let mut buffer = File::create(path)?;
+
+    serialize(&mut buffer, nodes)
+}
+
+/// serialize nodes to json with any writer, as an array of comments and words
+pub fn serialize(output: &mut impl Write, nodes: &Nodes) -> Result<()> {
+    let mut buf = BufWriter::new(output);
+
+    serde_json::to_writer_pretty(&mut buf, nodes)?;
+    writeln!(buf)?;
+
+    buf.flush()
+}
+
+#[test]
+fn serialize_json() {
+    use crate::data::*;
+
+    let nodes = Nodes(vec![
+        Node::Comment(" copy".into()),
+        Word::from(0b1000_101000_01).into(),
+    ]);
+    let mut json = Vec::new();
+
+    serialize(&mut json, &nodes).unwrap();
+
+    let value: serde_json::Value = serde_json::from_slice(&json).unwrap();
+
+    assert_eq!(
+        serde_json::json!([
+            { "comment": " copy" },
+            {
+                "word": {
+                    "raw": 0b1000_101000_01,
+                    "mnemonic": "sto",
+                    "addr": { "value": 0b101000, "kind": "parallel read" },
+                    "ctrl": { "value": 1, "name": "copy and shift out" }
+                }
+            }
+        ]),
+        value
+    );
+    assert_eq!(nodes, deserialize(&mut json.as_slice()).unwrap());
+}
+
+#[test]
+fn deserialize_json() {
+    let word = |raw: u32, mnemonic: &str, addr: u32, kind: &str| {
+        format!(
+            "[{{\"word\": {{\"raw\": {}, \"mnemonic\": \"{}\", \
+             \"addr\": {{\"value\": {}, \"kind\": \"{}\"}}, \
+             \"ctrl\": {{\"value\": 0, \"name\": \"null\"}}}}}}]",
+            raw, mnemonic, addr, kind
+        )
+    };
+    let read = |json: String| deserialize(&mut json.as_bytes());
+
+    assert_eq!(1, read(word(0x4e4, "ONE", 0o71, "rr")).unwrap().0.len());
+    assert!(read(word(0x4e5, "one", 0o71, "rr")).is_err());
+    assert!(read(word(0x4e4, "two", 0o71, "rr")).is_err());
+    assert!(read(word(0x4e4, "one", 0o71, "general")).is_err());
+    assert!(read(word(0x14e4, "one", 0o71, "rr")).is_err());
+    assert!(read(word(0x4e4, "one", 0o171, "rr")).is_err());
+    assert!(read("[{\"note\": \"\"}]".into()).is_err());
+}
@@ -0,0 +1,205 @@
+use super::binary::MAX_WORDS;
+use crate::data::{Nodes, *};
+use std::fs::File;
+use std::io::{prelude::*, BufReader, BufWriter, Error, ErrorKind, Result};
+use std::path::PathBuf;
+
+/// first line of an addressed image
+const HEADER: &str = "v3.0 hex words addressed";
+
+/// first line of an image with words and no addresses
+const PLAIN_HEADER: &str = "v3.0 hex words plain";
+
+/// first line of the older image format, also without addresses
+const RAW_HEADER: &str = "v2.0 raw";
+
+/// number of words on each line
+const LINE_LEN: usize = 16;
+
+/// read a file from disk and deserialize words from a logisim image
+pub fn read_file(path: PathBuf) -> Result<Nodes> {
+    let mut buffer = File::open(path)?;
+
+    deserialize(&mut buffer)
+}
+
+/// deserialize words from a logisim memory image with any reader
+///
+/// reads addressed and plain images, along with `N*value` runs of repeated
+/// words and `#` comments. any gaps left by addresses are filled with zero
+/// words.
+pub fn deserialize(input: &mut impl Read) -> Result<Nodes> {
+    let mut lines = BufReader::new(input).lines().enumerate();
+    let mut words = Vec::new();
+    let addressed = match lines.next() {
+        Some((_, line)) => match line?.trim() {
+            HEADER => true,
+            PLAIN_HEADER | RAW_HEADER => false,
+            _ => {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    "line 1: expected a logisim image header",
+                ))
+            }
+        },
+        None => false,
+    };
+
+    for (index, line) in lines {
+        let line = line?;
+        let invalid = |message: String| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("line {}: {}", index + 1, message),
+            )
+        };
+        let mut code = line.split('#').next().unwrap_or("");
+
+        if addressed && !code.trim().is_empty() {
+            let (address, values) = code
+                .split_once(':')
+                .ok_or_else(|| invalid("expected an address".into()))?;
+            let address =
+                usize::from_str_radix(address.trim(), 16).map_err(|_| {
+                    invalid(format!("invalid address `{}`", address))
+                })?;
+
+            if address < words.len() {
+                return Err(invalid(format!(
+                    "address `{:x}` is before the current word",
+                    address
+                )));
+            }
+
+            if address >= MAX_WORDS {
+                return Err(invalid(format!(
+                    "address `{:x}` is past the largest image of {} words",
+                    address, MAX_WORDS
+                )));
+            }
+
+            words.resize(address, 0);
+            code = values;
+        }
+
+        for token in code.split_whitespace() {
+            let (count, value) = match token.split_once('*') {
+                Some((count, value)) => (
+                    count.parse().map_err(|_| {
+                        invalid(format!("invalid run `{}`", token))
+                    })?,
+                    value,
+                ),
+                None => (1, token),
+            };
+            let bin = u32::from_str_radix(value, 16)
+                .ok()
+                .filter(|bin| *bin < 1 << 12)
+                .ok_or_else(|| invalid(format!("invalid word `{}`", token)))?;
+
+            if count > MAX_WORDS - words.len() {
+                return Err(invalid(format!(
+                    "run `{}` is past the largest image of {} words",
+                    token, MAX_WORDS
+                )));
+            }
+
+            words.extend(std::iter::repeat_n(bin, count));
+        }
+    }
+
+    Ok(Nodes(
+        words
+            .into_iter()
+            .map(|bin| Word::from(bin).into())
+            .collect(),
+    ))
+}
+
+/// serialize words to a logisim image and write a file to disk
+pub fn write_file(path: PathBuf, nodes: Nodes) -> Result<()> {
+    let mut buffer = File::create(path)?;
+
+    serialize(&mut buffer, nodes)
+}
+
+/// serialize nodes to a logisim `v3.0 hex words addressed` image with any
+/// writer
+pub fn serialize(output: &mut impl Write, nodes: Nodes) -> Result<()> {
+    let mut buf = BufWriter::new(output);
+    let Nodes(nodes) = nodes;
+    let words: Vec<u32> = nodes
+        .into_iter()
+        .filter_map(|node| Word::try_from(node).ok())
+        .map(u32::from)
+        .collect();
+    let width = format!("{:x}", words.len().saturating_sub(1)).len().max(2);
+
+    writeln!(buf, "{}", HEADER)?;
+
+    for (index, line) in words.chunks(LINE_LEN).enumerate() {
+        write!(buf, "{:0width$x}:", index * LINE_LEN, width = width)?;
+
+        for bin in line {
+            write!(buf, " {:03x}", bin)?;
+        }
+
+        writeln!(buf)?;
+    }
+
+    buf.flush()
+}
+
+#[test]
+fn serialize_image() {
+    let nodes = Nodes(vec![
+        Node::Comment(" skipped".into()),
+        Word::from(0x4e4).into(),
+        Word::from(0x8a1).into(),
+    ]);
+    let mut image = Vec::new();
+
+    serialize(&mut image, nodes).unwrap();
+
+    assert_eq!(
+        "v3.0 hex words addressed\n00: 4e4 8a1\n",
+        String::from_utf8(image).unwrap()
+    );
+
+    let nodes = Nodes((0..20).map(|bin| Word::from(bin).into()).collect());
+    let mut image = Vec::new();
+
+    serialize(&mut image, nodes.clone()).unwrap();
+
+    assert_eq!(nodes, deserialize(&mut image.as_slice()).unwrap());
+}
+
+#[test]
+fn deserialize_image() {
+    let image = "v3.0 hex words addressed\n\
+                 00: 4e4 2*8a1 # copy\n\
+                 08: 3*0 fff\n";
+    let expected: Vec<u32> =
+        vec![0x4e4, 0x8a1, 0x8a1, 0, 0, 0, 0, 0, 0, 0, 0, 0xfff];
+    let expected = Nodes(
+        expected
+            .into_iter()
+            .map(|bin| Word::from(bin).into())
+            .collect(),
+    );
+
+    assert_eq!(expected, deserialize(&mut image.as_bytes()).unwrap());
+
+    let plain = "v2.0 raw\n4e4 2*8a1\n";
+
+    assert_eq!(3, deserialize(&mut plain.as_bytes()).unwrap().0.len());
+    assert!(deserialize(&mut "4e4\n".as_bytes()).is_err());
+    assert!(deserialize(&mut "v2.0 raw\n1000\n".as_bytes()).is_err());
+    assert!(deserialize(&mut "v2.0 raw\nx*4e4\n".as_bytes()).is_err());
+
+    let far = "v3.0 hex words addressed\nffffffff: 4e4\n";
+    let long = "v2.0 raw\n4e4 4294967295*8a1\n";
+
+    assert!(deserialize(&mut far.as_bytes()).is_err());
+    assert!(deserialize(&mut long.as_bytes()).is_err());
+}
@@ -0,0 +1,221 @@
+use super::binary::MAX_WORDS;
+use crate::data::{Nodes, *};
+use std::fs::File;
+use std::io::{prelude::*, BufReader, BufWriter, Error, ErrorKind, Result};
+use std::path::PathBuf;
+
+/// number base of a memory image, as read by `$readmemh` or `$readmemb`
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Radix {
+    #[default]
+    Hex,
+    Bin,
+}
+
+impl Radix {
+    fn val(self) -> u32 {
+        match self {
+            Radix::Hex => 16,
+            Radix::Bin => 2,
+        }
+    }
+}
+
+/// read a file from disk and deserialize words from a memory image
+pub fn read_file(path: PathBuf, radix: Radix) -> Result<Nodes> {
+    let mut buffer = File::open(path)?;
+
+    deserialize(&mut buffer, radix)
+}
+
+/// deserialize words from a memory image with any reader
+///
+/// accepts what verilog does, values separated by whitespace with `_` between
+/// digits, `@` followed by a hex address to move to another word, and
+/// comments. line comments are kept as comment nodes, gaps left by addresses
+/// are filled with zero words.
+pub fn deserialize(input: &mut impl Read, radix: Radix) -> Result<Nodes> {
+    let mut nodes = Vec::new();
+    let mut words = 0;
+    let mut in_block = false;
+
+    for (index, line) in BufReader::new(input).lines().enumerate() {
+        let line = line?;
+        let invalid = |message: String| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("line {}: {}", index + 1, message),
+            )
+        };
+        let (code, comment) = strip_comments(&line, &mut in_block);
+
+        for token in code.split_whitespace() {
+            let digits = token.replace('_', "");
+
+            if let Some(address) = digits.strip_prefix('@') {
+                let address =
+                    usize::from_str_radix(address, 16).map_err(|_| {
+                        invalid(format!("invalid address `{}`", token))
+                    })?;
+
+                if address >= MAX_WORDS {
+                    return Err(invalid(format!(
+                        "address `{}` is past the largest image of {} words",
+                        token, MAX_WORDS
+                    )));
+                }
+
+                while words < address {
+                    nodes.push(Word::from(0).into());
+                    words += 1;
+                }
+
+                if words > address {
+                    return Err(invalid(format!(
+                        "address `{}` is before the current word",
+                        token
+                    )));
+                }
+
+                continue;
+            }
+
+            let bin = u32::from_str_radix(&digits, radix.val())
+                .ok()
+                .filter(|bin| *bin < 1 << 12)
+                .ok_or_else(|| invalid(format!("invalid word `{}`", token)))?;
+
+            nodes.push(Word::from(bin).into());
+            words += 1;
+        }
+
+        if let Some(comment) = comment {
+            nodes.push(Node::Comment(comment.trim_end().into()));
+        }
+    }
+
+    Ok(Nodes(nodes))
+}
+
+/// split a line into code, with block comments replaced by spaces, and the
+/// text of a line comment
+fn strip_comments<'a>(
+    line: &'a str,
+    in_block: &mut bool,
+) -> (String, Option<&'a str>) {
+    let mut code = String::new();
+    let mut rest = line;
+
+    loop {
+        if *in_block {
+            match rest.find("*/") {
+                Some(pos) => {
+                    rest = &rest[pos + 2..];
+                    *in_block = false;
+                    code.push(' ');
+                }
+                None => return (code, None),
+            }
+        } else {
+            match (rest.find("/*"), rest.find("//")) {
+                (Some(block), line) if line.is_none_or(|line| block < line) => {
+                    code.push_str(&rest[..block]);
+                    rest = &rest[block + 2..];
+                    *in_block = true;
+                }
+                (_, Some(line)) => {
+                    code.push_str(&rest[..line]);
+
+                    return (code, Some(&rest[line + 2..]));
+                }
+                _ => {
+                    code.push_str(rest);
+
+                    return (code, None);
+                }
+            }
+        }
+    }
+}
+
+/// serialize words to a memory image and write a file to disk
+pub fn write_file(path: PathBuf, nodes: Nodes, radix: Radix) -> Result<()> {
+    let mut buffer = File::create(path)?;
+
+    serialize(&mut buffer, nodes, radix)
+}
+
+/// serialize nodes to a memory image with any writer, one word per line
+pub fn serialize(
+    output: &mut impl Write,
+    nodes: Nodes,
+    radix: Radix,
+) -> Result<()> {
+    let mut buf = BufWriter::new(output);
+    let Nodes(nodes) = nodes;
+
+    for node in nodes {
+        match node {
+            Node::Comment(text) => writeln!(buf, "//{}", text)?,
+            Node::Word(inst, addr, ctrl) => {
+                let bin = u32::from(Word(inst, addr, ctrl));
+
+                match radix {
+                    Radix::Hex => writeln!(buf, "{:03x}", bin)?,
+                    Radix::Bin => writeln!(buf, "{:012b}", bin)?,
+                }
+            }
+        }
+    }
+
+    buf.flush()
+}
+
+#[test]
+fn serialize_mem() {
+    let nodes = Nodes(vec![
+        Node::Comment(" set all outputs".into()),
+        Word::from(0b0100_111001_00).into(),
+        Word::from(0b1000_101000_01).into(),
+    ]);
+
+    for (radix, expected) in [
+        (Radix::Hex, "// set all outputs\n4e4\n8a1\n"),
+        (
+            Radix::Bin,
+            "// set all outputs\n010011100100\n100010100001\n",
+        ),
+    ] {
+        let mut mem = Vec::new();
+
+        serialize(&mut mem, nodes.clone(), radix).unwrap();
+
+        assert_eq!(expected, String::from_utf8(mem).unwrap());
+        assert_eq!(
+            nodes,
+            deserialize(&mut expected.as_bytes(), radix).unwrap()
+        );
+    }
+}
+
+#[test]
+fn deserialize_mem() {
+    let mem = "4e4 8a1 /* two\nwords */ @4\n1_2c // last\n";
+    let expected = Nodes(vec![
+        Word::from(0x4e4).into(),
+        Word::from(0x8a1).into(),
+        Word::from(0).into(),
+        Word::from(0).into(),
+        Word::from(0x12c).into(),
+        Node::Comment(" last".into()),
+    ]);
+
+    assert_eq!(
+        expected,
+        deserialize(&mut mem.as_bytes(), Radix::Hex).unwrap()
+    );
+    assert!(deserialize(&mut "1000\n".as_bytes(), Radix::Hex).is_err());
+    assert!(deserialize(&mut "102\n".as_bytes(), Radix::Bin).is_err());
+    assert!(deserialize(&mut "4e4\n@0\n".as_bytes(), Radix::Hex).is_err());
+    assert!(deserialize(&mut "@ffffffff\n".as_bytes(), Radix::Hex).is_err());
+}
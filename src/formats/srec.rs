@@ -0,0 +1,288 @@
+use super::binary;
+pub use super::binary::Layout;
+use crate::data::Nodes;
+use std::fs::File;
+use std::io::{prelude::*, BufReader, BufWriter, Error, ErrorKind, Result};
+use std::path::PathBuf;
+
+/// number of data bytes in each record
+const RECORD_LEN: usize = 16;
+
+/// text of the header record
+const HEADER: &[u8] = b"ue14500";
+
+/// how s-records are read and written
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Options {
+    /// how words are laid out in the bytes of the image
+    pub layout: Layout,
+    /// address of the first byte of the image
+    pub load_address: u32,
+}
+
+impl Options {
+    /// set how words are laid out in bytes
+    pub fn with_layout(self, layout: Layout) -> Options {
+        Options { layout, ..self }
+    }
+
+    /// set the address the image is loaded at
+    pub fn with_load_address(self, load_address: u32) -> Options {
+        Options {
+            load_address,
+            ..self
+        }
+    }
+}
+
+/// read a file from disk and deserialize words from s-records
+pub fn read_file(path: PathBuf, options: Options) -> Result<Nodes> {
+    let mut buffer = File::open(path)?;
+
+    deserialize(&mut buffer, options)
+}
+
+/// deserialize words from s-records with any reader
+///
+/// the image starts at the load address, any gaps between records are filled
+/// with zero bytes. every checksum is validated, along with the record count
+/// when there is one.
+pub fn deserialize(input: &mut impl Read, options: Options) -> Result<Nodes> {
+    let bytes = read_records(BufReader::new(input), options.load_address)?;
+
+    binary::from_bytes(&bytes, options.layout)
+}
+
+/// serialize words to s-records and write a file to disk
+pub fn write_file(path: PathBuf, nodes: Nodes, options: Options) -> Result<()> {
+    let mut buffer = File::create(path)?;
+
+    serialize(&mut buffer, nodes, options)
+}
+
+/// serialize nodes to s-records with any writer
+///
+/// images that fit below 64KiB use S1 records with a closing S9, larger ones
+/// use S2 records with a closing S8.
+pub fn serialize(
+    output: &mut impl Write,
+    nodes: Nodes,
+    options: Options,
+) -> Result<()> {
+    let bytes = binary::to_bytes(nodes, options.layout)?;
+    let end = options.load_address as usize + bytes.len();
+
+    if end > 0x100_0000 {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "image does not fit in a 24-bit address space",
+        ));
+    }
+
+    let (data, termination, width) = if end > 0x1_0000 {
+        (b'2', b'8', 3)
+    } else {
+        (b'1', b'9', 2)
+    };
+    let mut output = BufWriter::new(output);
+    let mut count = 0;
+
+    write_record(&mut output, b'0', 0, 2, HEADER)?;
+
+    for (index, chunk) in bytes.chunks(RECORD_LEN).enumerate() {
+        let address = options.load_address + (index * RECORD_LEN) as u32;
+
+        write_record(&mut output, data, address, width, chunk)?;
+        count += 1;
+    }
+
+    if count <= 0xFFFF {
+        write_record(&mut output, b'5', count, 2, &[])?;
+    }
+
+    write_record(&mut output, termination, 0, width, &[])?;
+    output.flush()
+}
+
+fn write_record(
+    output: &mut impl Write,
+    kind: u8,
+    address: u32,
+    width: usize,
+    data: &[u8],
+) -> Result<()> {
+    let mut record = vec![(width + data.len() + 1) as u8];
+
+    record.extend_from_slice(&address.to_be_bytes()[4 - width..]);
+    record.extend_from_slice(data);
+    record.push(checksum(&record));
+
+    write!(output, "S{}", kind as char)?;
+
+    for byte in record {
+        write!(output, "{:02X}", byte)?;
+    }
+
+    writeln!(output)
+}
+
+fn read_records(input: impl BufRead, load_address: u32) -> Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    let mut count = 0;
+
+    for (index, line) in input.lines().enumerate() {
+        let line = line?;
+        let line = line.trim();
+        let invalid = |message: &str| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("line {}: {}", index + 1, message),
+            )
+        };
+
+        if line.is_empty() {
+            continue;
+        }
+
+        let (kind, hex) = line
+            .strip_prefix('S')
+            .and_then(|line| line.split_at_checked(1))
+            .ok_or_else(|| invalid("expected a record starting with `S`"))?;
+        let record = (0..hex.len())
+            .step_by(2)
+            .map(|pos| {
+                hex.get(pos..pos + 2)
+                    .and_then(|byte| u8::from_str_radix(byte, 16).ok())
+            })
+            .collect::<Option<Vec<u8>>>()
+            .ok_or_else(|| invalid("expected hexadecimal bytes"))?;
+        let width = match kind {
+            "0" | "1" | "5" | "9" => 2,
+            "2" | "6" | "8" => 3,
+            "3" | "7" => 4,
+            _ => return Err(invalid("unsupported record type")),
+        };
+
+        if record.len() < width + 2 || record.len() != record[0] as usize + 1 {
+            return Err(invalid("record length does not match its data"));
+        }
+
+        if checksum(&record[..record.len() - 1]) != record[record.len() - 1] {
+            return Err(invalid("checksum does not match"));
+        }
+
+        let address = record[1..=width]
+            .iter()
+            .fold(0, |address, byte| address << 8 | u32::from(*byte));
+        let data = &record[width + 1..record.len() - 1];
+
+        match kind {
+            "1" | "2" | "3" => {
+                let start =
+                    address.checked_sub(load_address).ok_or_else(|| {
+                        invalid("record is below the load address")
+                    })? as usize;
+
+                if start + data.len() > binary::MAX_BYTES {
+                    return Err(invalid(&format!(
+                        "address {:#x} is past the largest image of {} bytes",
+                        address,
+                        binary::MAX_BYTES
+                    )));
+                }
+
+                if bytes.len() < start + data.len() {
+                    bytes.resize(start + data.len(), 0);
+                }
+
+                bytes[start..start + data.len()].copy_from_slice(data);
+                count += 1;
+            }
+            "5" | "6" if address != count => {
+                return Err(invalid("record count does not match"));
+            }
+            "7" | "8" | "9" => break,
+            _ => {}
+        }
+    }
+
+    Ok(bytes)
+}
+
+/// ones' complement of the sum of every byte in a record
+fn checksum(record: &[u8]) -> u8 {
+    !record.iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte))
+}
+
+#[test]
+fn serialize_records() {
+    use crate::data::*;
+
+    let nodes = Nodes(vec![
+        Node::Comment(" skipped".into()),
+        Word::from(0b1010_111111_01).into(),
+        Word::from(0b0001_000010_10).into(),
+    ]);
+    let mut srec = Vec::new();
+
+    serialize(&mut srec, nodes.clone(), Options::default()).unwrap();
+
+    assert_eq!(
+        "S00A00007565313435303021\n\
+         S10700000AFD010AE6\n\
+         S5030001FB\n\
+         S9030000FC\n",
+        String::from_utf8(srec).unwrap()
+    );
+
+    let mut srec = Vec::new();
+    let options = Options::default()
+        .with_layout(Layout::Packed)
+        .with_load_address(0x1_2000);
+
+    serialize(&mut srec, nodes, options).unwrap();
+
+    assert_eq!(
+        "S00A00007565313435303021\n\
+         S207012000AFD10A4D\n\
+         S5030001FB\n\
+         S804000000FB\n",
+        String::from_utf8(srec).unwrap()
+    );
+}
+
+#[test]
+fn deserialize_round_trip() {
+    use super::binary::sample_nodes;
+
+    let nodes = sample_nodes(40);
+
+    for layout in [Layout::Word, Layout::Packed] {
+        for load_address in [0, 0x8000, 0xFFF0] {
+            let options = Options::default()
+                .with_layout(layout)
+                .with_load_address(load_address);
+            let mut srec = Vec::new();
+
+            serialize(&mut srec, nodes.clone(), options).unwrap();
+
+            let read = deserialize(&mut srec.as_slice(), options).unwrap();
+
+            assert_eq!(nodes, read);
+        }
+    }
+
+    let options = Options::default();
+    let bad_checksum = "S10700000AFD010AE7\nS9030000FC\n";
+    let bad_count = "S10700000AFD010AE6\nS5030002FA\nS9030000FC\n";
+
+    assert!(deserialize(&mut bad_checksum.as_bytes(), options).is_err());
+    assert!(deserialize(&mut bad_count.as_bytes(), options).is_err());
+
+    let far = "S306FFFFFF0000FC\nS70500000000FA\n";
+    let error = deserialize(&mut far.as_bytes(), options).unwrap_err();
+
+    assert!(error
+        .to_string()
+        .starts_with("line 1: address 0xffffff00 is past"));
+}
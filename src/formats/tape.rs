@@ -0,0 +1,385 @@
+use super::assembly::{writer, Style};
+use crate::data::{Nodes, *};
+use std::fs::File;
+use std::io::{prelude::*, BufReader, BufWriter, Error, ErrorKind, Result};
+use std::path::PathBuf;
+
+/// drawn for a punched hole
+const HOLE: char = 'o';
+
+/// drawn where a hole could be punched but is not
+const BLANK: char = '.';
+
+/// drawn for the sprocket hole in every frame
+const SPROCKET: char = ':';
+
+/// drawn for both edges of the tape
+const EDGE: char = '|';
+
+/// how words are punched into frames of tape
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Options {
+    /// number of blank frames before the first word
+    pub leader: usize,
+    /// number of blank frames after the last word
+    pub trailer: usize,
+    /// number of frames each word is punched across
+    pub frames_per_word: usize,
+}
+
+impl Default for Options {
+    fn default() -> Options {
+        Options {
+            leader: 16,
+            trailer: 16,
+            frames_per_word: 2,
+        }
+    }
+}
+
+impl Options {
+    /// set the number of blank frames before the first word
+    pub fn with_leader(self, leader: usize) -> Options {
+        Options { leader, ..self }
+    }
+
+    /// set the number of blank frames after the last word
+    pub fn with_trailer(self, trailer: usize) -> Options {
+        Options { trailer, ..self }
+    }
+
+    /// set the number of frames each word is punched across
+    pub fn with_frames_per_word(self, frames_per_word: usize) -> Options {
+        Options {
+            frames_per_word,
+            ..self
+        }
+    }
+
+    /// number of holes across each frame, not counting the sprocket
+    pub fn channels(&self) -> usize {
+        12 / self.frames_per_word
+    }
+
+    /// check that each word fills a whole number of frames of at most eight
+    /// channels
+    pub fn validate(&self) -> Result<()> {
+        if self.frames_per_word == 0
+            || 12 % self.frames_per_word != 0
+            || self.channels() > 8
+        {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "frames per word must be one of 2, 3, 4, 6 or 12",
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// number of channels right of the sprocket, like the three low
+    /// channels of eight and five level tape
+    ///
+    /// always at least one, so a single channel tape still has a sprocket.
+    fn low_channels(&self) -> usize {
+        match self.channels() {
+            channels if channels >= 5 => 3,
+            channels => (channels / 2).max(1),
+        }
+    }
+
+    /// split each word into frames, most significant bits first, with blank
+    /// frames for the leader and trailer
+    fn frames(&self, nodes: Nodes) -> Result<Vec<(u8, Option<Node>)>> {
+        self.validate()?;
+
+        let channels = self.channels();
+        let mask = (1 << channels) - 1;
+        let Nodes(nodes) = nodes;
+        let mut frames = vec![(0, None); self.leader];
+
+        for node in nodes {
+            if let Node::Word(inst, addr, ctrl) = node {
+                let bin = u32::from(Word(inst, addr, ctrl));
+
+                for frame in 0..self.frames_per_word {
+                    let shift = (self.frames_per_word - 1 - frame) * channels;
+                    let node = (frame == 0).then(|| node.clone());
+
+                    frames.push(((bin >> shift & mask) as u8, node));
+                }
+            }
+        }
+
+        frames.extend(vec![(0, None); self.trailer]);
+
+        Ok(frames)
+    }
+
+    /// merge frames back into words, dropping the leader and trailer
+    fn words(&self, frames: &[u8]) -> Result<Nodes> {
+        self.validate()?;
+
+        let invalid = |message: &str| {
+            Error::new(ErrorKind::InvalidData, message.to_string())
+        };
+        let end = frames
+            .len()
+            .checked_sub(self.trailer)
+            .filter(|end| *end >= self.leader)
+            .ok_or_else(|| {
+                invalid("tape is shorter than its leader and trailer")
+            })?;
+        let frames = &frames[self.leader..end];
+
+        if !frames.len().is_multiple_of(self.frames_per_word) {
+            return Err(invalid("tape does not hold a whole number of words"));
+        }
+
+        Ok(Nodes(
+            frames
+                .chunks(self.frames_per_word)
+                .map(|word| {
+                    let bin = word.iter().fold(0, |bin, frame| {
+                        bin << self.channels() | u32::from(*frame)
+                    });
+
+                    Word::from(bin).into()
+                })
+                .collect(),
+        ))
+    }
+}
+
+/// read a file from disk and deserialize words from tape text
+pub fn read_file(path: PathBuf, options: Options) -> Result<Nodes> {
+    let mut buffer = File::open(path)?;
+
+    deserialize(&mut buffer, options)
+}
+
+/// deserialize words from tape text with any reader
+///
+/// each frame is read from between the first pair of `|` edges on a line,
+/// where `o` is a hole and `.` or a space is not, ignoring the sprocket. lines
+/// without edges and anything after the closing edge are skipped, so notes
+/// can be written alongside a transcribed tape.
+pub fn deserialize(input: &mut impl Read, options: Options) -> Result<Nodes> {
+    let mut frames = Vec::new();
+
+    for (index, line) in BufReader::new(input).lines().enumerate() {
+        let line = line?;
+        let invalid = |message: &str| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("line {}: {}", index + 1, message),
+            )
+        };
+        let frame = match line.split(EDGE).nth(1) {
+            Some(frame) if line.matches(EDGE).count() >= 2 => frame,
+            _ => continue,
+        };
+        let mut holes = Vec::new();
+
+        for hole in frame.chars() {
+            match hole {
+                'o' | 'O' => holes.push(true),
+                '.' | ' ' => holes.push(false),
+                SPROCKET => {}
+                _ => return Err(invalid("expected `o` or `.` for each hole")),
+            }
+        }
+
+        if holes.len() != options.channels() {
+            return Err(invalid(&format!(
+                "expected {} holes in a frame",
+                options.channels()
+            )));
+        }
+
+        frames.push(
+            holes
+                .into_iter()
+                .fold(0, |frame, hole| frame << 1 | u8::from(hole)),
+        );
+    }
+
+    options.words(&frames)
+}
+
+/// serialize words to tape text and write a file to disk
+pub fn write_file(path: PathBuf, nodes: Nodes, options: Options) -> Result<()> {
+    let mut buffer = File::create(path)?;
+
+    serialize(&mut buffer, nodes, options)
+}
+
+/// serialize nodes to tape text with any writer, one frame per line
+///
+/// holes are drawn as `o` and blanks as `.` with the sprocket as `:`, the
+/// first frame of each word is followed by its assembly.
+pub fn serialize(
+    output: &mut impl Write,
+    nodes: Nodes,
+    options: Options,
+) -> Result<()> {
+    let mut buf = BufWriter::new(output);
+    let channels = options.channels();
+    let sprocket = channels - options.low_channels();
+    let style = Style::default().with_symbols(true);
+
+    for (frame, node) in options.frames(nodes)? {
+        let mut line = String::from(EDGE);
+
+        for channel in 0..channels {
+            if channel == sprocket {
+                line.push(SPROCKET);
+            }
+
+            let hole = frame >> (channels - 1 - channel) & 1 == 1;
+
+            line.push(if hole { HOLE } else { BLANK });
+        }
+
+        line.push(EDGE);
+
+        if let Some(node) = node {
+            line.push_str("  ");
+            line.push_str(&writer::format_node(&node, &style));
+        }
+
+        writeln!(buf, "{}", line)?;
+    }
+
+    buf.flush()
+}
+
+/// read a file from disk and deserialize words from raw tape frames
+pub fn read_raw_file(path: PathBuf, options: Options) -> Result<Nodes> {
+    let mut buffer = File::open(path)?;
+
+    deserialize_raw(&mut buffer, options)
+}
+
+/// deserialize words from raw tape frames with any reader, one byte per
+/// frame with the holes in the low bits
+pub fn deserialize_raw(
+    input: &mut impl Read,
+    options: Options,
+) -> Result<Nodes> {
+    let mut frames = Vec::new();
+
+    input.read_to_end(&mut frames)?;
+
+    options.words(&frames)
+}
+
+/// serialize words to raw tape frames and write a file to disk
+pub fn write_raw_file(
+    path: PathBuf,
+    nodes: Nodes,
+    options: Options,
+) -> Result<()> {
+    let mut buffer = File::create(path)?;
+
+    serialize_raw(&mut buffer, nodes, options)
+}
+
+/// serialize nodes to raw tape frames with any writer, one byte per frame
+/// with the holes in the low bits
+pub fn serialize_raw(
+    output: &mut impl Write,
+    nodes: Nodes,
+    options: Options,
+) -> Result<()> {
+    let frames: Vec<u8> = options
+        .frames(nodes)?
+        .into_iter()
+        .map(|(frame, _)| frame)
+        .collect();
+
+    output.write_all(&frames)?;
+    output.flush()
+}
+
+#[test]
+fn serialize_tape() {
+    let nodes = Nodes(vec![
+        Node::Comment(" skipped".into()),
+        Word::from(0b0100_111001_00).into(),
+        Word::from(0b1000_101000_01).into(),
+    ]);
+    let options = Options::default().with_leader(1).with_trailer(2);
+    let mut tape = Vec::new();
+
+    serialize(&mut tape, nodes.clone(), options).unwrap();
+
+    assert_eq!(
+        "|...:...|\n\
+         |.o.:.oo|  ONE  RR\n\
+         |o..:o..|\n\
+         |o..:.o.|  STO  PR0      copy\n\
+         |o..:..o|\n\
+         |...:...|\n\
+         |...:...|\n",
+        String::from_utf8(tape.clone()).unwrap()
+    );
+    assert_eq!(
+        nodes.0[1..],
+        deserialize(&mut tape.as_slice(), options).unwrap().0
+    );
+
+    let mut raw = Vec::new();
+
+    serialize_raw(&mut raw, nodes.clone(), options).unwrap();
+
+    assert_eq!(vec![0, 0o23, 0o44, 0o42, 0o41, 0, 0], raw);
+    assert_eq!(
+        nodes.0[1..],
+        deserialize_raw(&mut raw.as_slice(), options).unwrap().0
+    );
+}
+
+#[test]
+fn deserialize_tape() {
+    let options = Options::default()
+        .with_leader(0)
+        .with_trailer(0)
+        .with_frames_per_word(3);
+    let tape = "transcribed from reel 2\n\
+                | o:.o|  ONE\n\
+                |oo:o.|\n\
+                |..:..|\n";
+
+    assert_eq!(
+        Nodes(vec![Word::from(0b0101_111000_00).into()]),
+        deserialize(&mut tape.as_bytes(), options).unwrap()
+    );
+    assert!(deserialize(&mut "|o.:..|\n".as_bytes(), options).is_err());
+    assert!(deserialize(&mut "|o.:...|\n".as_bytes(), options).is_err());
+    assert!(deserialize(&mut "|x.:..|\n".as_bytes(), options).is_err());
+
+    let options = options.with_frames_per_word(12);
+    let mut tape = Vec::new();
+
+    serialize(&mut tape, Nodes(vec![Word::from(1).into()]), options).unwrap();
+
+    let tape = String::from_utf8(tape).unwrap();
+
+    assert_eq!(
+        12,
+        tape.lines().filter(|line| line.starts_with("|:")).count()
+    );
+    assert_eq!(
+        Nodes(vec![Word::from(1).into()]),
+        deserialize(&mut tape.as_bytes(), options).unwrap()
+    );
+    assert!(Options::default()
+        .with_frames_per_word(1)
+        .validate()
+        .is_err());
+    assert!(Options::default()
+        .with_frames_per_word(5)
+        .validate()
+        .is_err());
+}
@@ -1,4 +1,5 @@
 #![allow(clippy::unusual_byte_groupings)]
 
 pub mod data;
-pub mod formats;
\ No newline at end of file
+pub mod emulator;
+pub mod formats;